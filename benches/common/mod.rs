@@ -0,0 +1,49 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+//! Fixtures and timing shared by the benchmarks.
+
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+use nvm_bevyutil::{math::prelude::Vec2, collision::Shape};
+
+pub const ITERATIONS: u32 = 200;
+
+/// Cycles through every kind of shape, covering each triangle orientation
+pub fn shape_of_kind(index: usize, origin: Vec2, size: Vec2) -> Shape {
+    match index % 4 {
+        0 => Shape::ellipse(origin, size),
+        1 => Shape::circle(origin, size.x),
+        2 => Shape::rectangle(origin, size),
+        _ => Shape::triangle(origin, size, index % 8 < 4, index % 16 < 8),
+    }
+}
+
+/// A grid of mixed shapes, 64 to a row
+pub fn shapes(count: usize) -> Vec<Shape> {
+    (0..count).map(|index| {
+        let origin = Vec2::new((index % 64) as f32, (index/64) as f32);
+        let size = Vec2::new(0.5 + (index % 3) as f32*0.25, 0.5 + (index % 5) as f32*0.25);
+        shape_of_kind(index, origin, size)
+    }).collect()
+}
+
+/// Times a function, returning the average duration of an iteration
+pub fn time<F: FnMut()>(mut func: F) -> Duration {
+    // Warm up caches before measuring
+    (0..ITERATIONS/10).for_each(|_| func());
+    let start = Instant::now();
+    (0..ITERATIONS).for_each(|_| func());
+    start.elapsed()/ITERATIONS
+}
+
+/// Prints two timings and how much faster the second is
+pub fn report(name: &str, (a_name, a): (&str, Duration), (b_name, b): (&str, Duration)) {
+    println!(
+        "{:<26} {} {:>10.2?}  {} {:>10.2?}  speedup {:.2}x",
+        name, a_name, a, b_name, b, a.as_secs_f64()/b.as_secs_f64(),
+    );
+}
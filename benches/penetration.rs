@@ -0,0 +1,47 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+//! Times penetration and time of impact between pairs of neighbouring shapes,
+//! grouped by how many of each pair are ellipses, as ellipses need a search
+//! for their axis. Run with `cargo bench --bench penetration`.
+
+use std::hint::black_box;
+
+use nvm_bevyutil::{
+    math::prelude::Vec2,
+    collision::{Shape, ShapeKind},
+};
+
+mod common;
+use common::{shapes, time};
+
+const SHAPES: usize = 4096;
+
+/// Pairs each shape with its neighbours to the right and above, which overlap
+fn pairs(shapes: &[Shape], ellipses: usize) -> Vec<(Shape, Shape)> {
+    let is_ellipse = |shape: &Shape| (shape.shape == ShapeKind::Ellipse) as usize;
+    (0..shapes.len()).flat_map(|index| [index + 1, index + 64].map(|other| (index, other)))
+        .filter_map(|(index, other)| Some((shapes[index], *shapes.get(other)?)))
+        .filter(|(a, b)| is_ellipse(a) + is_ellipse(b) == ellipses)
+        .collect()
+}
+
+fn main() {
+    let shapes = shapes(SHAPES);
+    let motion = Vec2::new(1.5, -0.75);
+    for (name, ellipses) in [("polygon pairs", 0), ("ellipse and polygon", 1), ("ellipse pairs", 2)] {
+        let pairs = pairs(&shapes, ellipses);
+        let per_pair = |total: std::time::Duration| total/pairs.len() as u32;
+        let penetration = time(|| for (a, b) in black_box(&pairs) {
+            black_box(a.get_penetration(b));
+        });
+        let impact = time(|| for (a, b) in black_box(&pairs) {
+            black_box(a.time_of_impact(black_box(motion), b));
+        });
+        println!(
+            "{:<20} {:>5} pairs  penetration {:>10.2?}  time of impact {:>10.2?}",
+            name, pairs.len(), per_pair(penetration), per_pair(impact),
+        );
+    }
+}
@@ -0,0 +1,78 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+//! Compares projecting shapes one at a time against the batch API.
+//! Run with `cargo bench --bench projection`.
+
+use std::{hint::black_box, time::Duration};
+
+use nvm_bevyutil::{
+    math::prelude::Vec2,
+    collision::{Shape, ShapeBatch, ProjectionBatch, Projection, project_on_axes},
+};
+
+mod common;
+use common::{shapes, time};
+
+const SHAPES: usize = 4096;
+const AXES:   usize = 64;
+
+fn axes() -> Vec<Vec2> {
+    (0..AXES).map(|index| Vec2::new((index as f32*0.1).cos(), (index as f32*0.1).sin())).collect()
+}
+
+fn report(name: &str, scalar: Duration, batch: Duration) {
+    common::report(name, ("scalar", scalar), ("batch", batch));
+}
+
+fn main() {
+    let (shapes, axes) = (shapes(SHAPES), axes());
+    let batch: ShapeBatch = shapes.iter().copied().collect();
+    let mut out = ProjectionBatch::new();
+    let mut scalar_out = Vec::<Projection>::with_capacity(SHAPES);
+
+    let axis = axes[3];
+    report(
+        "many shapes, one axis",
+        time(|| {
+            scalar_out.clear();
+            scalar_out.extend(black_box(&shapes).iter().map(|shape| shape.project_on(black_box(axis))));
+            black_box(&scalar_out);
+        }),
+        time(|| {
+            black_box(&batch).project_on(black_box(axis), &mut out);
+            black_box(&out);
+        }),
+    );
+
+    // Tilemaps push one kind of shape in a run, which skips reordering
+    let rectangles: Vec<Shape> = shapes.iter().map(|shape| Shape::rectangle(shape.origin, shape.hbound)).collect();
+    let batch: ShapeBatch = rectangles.iter().copied().collect();
+    report(
+        "many rectangles, one axis",
+        time(|| {
+            scalar_out.clear();
+            scalar_out.extend(black_box(&rectangles).iter().map(|shape| shape.project_on(black_box(axis))));
+            black_box(&scalar_out);
+        }),
+        time(|| {
+            black_box(&batch).project_on(black_box(axis), &mut out);
+            black_box(&out);
+        }),
+    );
+
+    let shape = shapes[1];
+    report(
+        "one shape, many axes",
+        time(|| {
+            scalar_out.clear();
+            scalar_out.extend(black_box(&axes).iter().map(|&axis| black_box(shape).project_on(axis)));
+            black_box(&scalar_out);
+        }),
+        time(|| {
+            project_on_axes(black_box(&shape), black_box(&axes), &mut out);
+            black_box(&out);
+        }),
+    );
+}
@@ -4,6 +4,9 @@
 
 use bevy::prelude::PluginGroup;
 
+#[cfg(test)]
+mod test;
+
 mod orthographic_camera_scaler;
 pub use orthographic_camera_scaler::*;
 
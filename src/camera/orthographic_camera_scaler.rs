@@ -5,28 +5,38 @@
 use bevy::{
     ecs::{
         component::Component,
+        reflect::ReflectComponent,
         system::{Query, Res}
     },
+    reflect::Reflect,
     app::Plugin,
     render::camera::{
         Camera, 
         OrthographicProjection, 
+        RenderTarget,
         ScalingMode
     }, 
     window::Windows
 };
+#[cfg(feature = "serde")]
+use bevy::reflect::ReflectDeserialize;
 
 #[derive(Default, Clone, Copy)]
 pub struct OrthographicCameraScalerPlugin;
 
 impl Plugin for OrthographicCameraScalerPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_system(orthographic_camera_scaler);
+        app.register_type::<OrthographicScaleMode>()
+            .add_system(orthographic_camera_scaler);
     }
 }
 
-#[derive(Component)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Component, Reflect)]
+#[cfg_attr(not(feature = "serde"), reflect_value(Component, PartialEq))]
+#[cfg_attr(feature = "serde", reflect_value(Component, PartialEq, Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrthographicScaleMode {
+    #[default]
     None,
     Width(f32),
     Height(f32),
@@ -39,9 +49,12 @@ pub fn orthographic_camera_scaler(
     mut query: Query<(&OrthographicScaleMode, &Camera, &mut OrthographicProjection)>
 ) {
     for (scale, camera, mut projection,) in query.iter_mut() {
-        let a = {
-            let window = windows.get(camera.window).unwrap();
-            window.width()/window.height()
+        let a = match camera.target {
+            RenderTarget::Window(id) => {
+                let window = windows.get(id).unwrap();
+                window.width()/window.height()
+            },
+            _ => continue,
         };
 
         let (w, h, contain) = match *scale {
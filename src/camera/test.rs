@@ -0,0 +1,30 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::{
+    app::App,
+    reflect::TypeRegistryArc,
+    render::camera::{Camera, OrthographicProjection, RenderTarget, ScalingMode},
+    window::Windows,
+};
+
+use super::*;
+
+#[test]
+fn orthographic_camera_scaler_targets() {
+    let mut app = App::new();
+    app.init_resource::<Windows>().add_plugin(OrthographicCameraScalerPlugin);
+    assert!(app.world.resource::<TypeRegistryArc>().read().get(std::any::TypeId::of::<OrthographicScaleMode>()).is_some());
+
+    // Cameras rendering to an image have no window to take the aspect ratio from
+    let camera = app.world.spawn()
+        .insert(Camera{ target: RenderTarget::Image(Default::default()), ..Default::default() })
+        .insert(OrthographicProjection::default())
+        .insert(OrthographicScaleMode::Width(10.0))
+        .id();
+    app.update();
+
+    let projection = app.world.get::<OrthographicProjection>(camera).unwrap();
+    assert!(matches!(projection.scaling_mode, ScalingMode::WindowSize));
+}
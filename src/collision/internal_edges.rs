@@ -0,0 +1,86 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use crate::math::Scalar;
+
+use super::ProjectionOf;
+
+/// One of the axis-aligned sides of a shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Left, Side::Right, Side::Bottom, Side::Top];
+
+    /// The side of a neighbour facing this side, ie. left becomes right
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Left   => Side::Right,
+            Side::Right  => Side::Left,
+            Side::Bottom => Side::Top,
+            Side::Top    => Side::Bottom,
+        }
+    }
+}
+
+/// The parts of the axis-aligned sides of a shape which are shared with a
+/// neighbouring shape, ie. between adjacent tiles. Nothing can be pushed out
+/// through an internal edge, so contacts lying entirely within one are ignored
+/// when resolving penetrations.
+///
+/// Each side stores spans in world space, along the y axis for the left and
+/// right sides and along the x axis for the bottom and top.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InternalEdgesOf<S: Scalar> {
+    spans: [Vec<ProjectionOf<S>>; 4],
+}
+
+/// The internal edges of a collider, see [super::Shape::get_penetration_with_edges]
+pub type InternalEdges = InternalEdgesOf<f32>;
+
+impl<S: Scalar> InternalEdgesOf<S> {
+    pub fn new() -> Self {
+        Self{ spans: Default::default() }
+    }
+
+    /// Marks part of a side as internal, merging it with any spans it touches
+    ///
+    /// # Arguments
+    ///
+    /// * `side` The side the span lies on
+    /// * `span` The internal part of the side, in world space
+    pub fn add(&mut self, side: Side, mut span: ProjectionOf<S>) {
+        let spans = &mut self.spans[side as usize];
+        spans.retain(|&other| other.are_separate(span) || {
+            span = ProjectionOf::covering_both(span, other);
+            false
+        });
+        let index = spans.partition_point(|other| other.near() < span.near());
+        spans.insert(index, span);
+    }
+
+    /// The internal spans of a side, sorted and separate from each other
+    pub fn spans(&self, side: Side) -> &[ProjectionOf<S>] {
+        &self.spans[side as usize]
+    }
+
+    /// Checks if a contact along a side lies entirely within an internal span
+    ///
+    /// # Arguments
+    ///
+    /// * `side` The side the contact is on
+    /// * `contact` The range of the side touched, in world space
+    pub fn contains(&self, side: Side, contact: ProjectionOf<S>) -> bool {
+        self.spans(side).iter().any(|span| span.contains(contact))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.iter().all(Vec::is_empty)
+    }
+}
@@ -12,4 +12,21 @@ mod projection;
 pub use projection::*;
 
 mod overlap;
-pub use overlap::*;
\ No newline at end of file
+pub use overlap::*;
+
+mod moving_projection;
+pub use moving_projection::*;
+
+mod shape_batch;
+pub use shape_batch::*;
+
+mod internal_edges;
+pub use internal_edges::*;
+
+#[cfg(feature = "serde")]
+mod shape_serde;
+
+#[cfg(feature = "render")]
+mod shape_mesh;
+#[cfg(feature = "render")]
+pub use shape_mesh::*;
@@ -0,0 +1,63 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use crate::math::Scalar;
+
+use super::ProjectionOf;
+
+/// A projection moving along its axis at a constant velocity, used to find
+/// when two shapes start and stop overlapping along an axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovingProjectionOf<S> {
+    pub projection: ProjectionOf<S>,
+    pub velocity:   S,
+}
+
+/// A [MovingProjectionOf] using `f32`
+pub type MovingProjection = MovingProjectionOf<f32>;
+
+impl<S: Scalar> MovingProjectionOf<S> {
+    /// Construct a new moving projection.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `projection` The projection at time zero
+    /// * `velocity` The distance moved along the axis per unit of time
+    pub fn new(projection: ProjectionOf<S>, velocity: S) -> Self {
+        Self{ projection, velocity }
+    }
+
+    /// Gets the projection at the given time
+    pub fn at(self, time: S) -> ProjectionOf<S> {
+        let offset = self.velocity*time;
+        ProjectionOf::new_unchecked(self.projection.near() + offset, self.projection.far() + offset)
+    }
+
+    /// Calculates the times this projection starts and stops overlapping a
+    /// stationary projection, including exact contact. Times may be negative
+    /// or beyond the range of interest, and are infinite when not moving.
+    /// Returns `None` if they never overlap.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` The stationary projection
+    pub fn get_entry_exit(self, other: ProjectionOf<S>) -> Option<(S, S)> {
+        if self.velocity.is_zero() {
+            return if self.projection.are_separate(other) { None } else { Some((S::neg_infinity(), S::infinity())) };
+        }
+
+        let a = (other.near() - self.projection.far() )/self.velocity;
+        let b = (other.far()  - self.projection.near())/self.velocity;
+        Some((a.min(b), a.max(b)))
+    }
+
+    /// Like [Self::get_entry_exit], but with both projections moving
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` The other moving projection
+    pub fn get_entry_exit_moving(self, other: Self) -> Option<(S, S)> {
+        Self::new(self.projection, self.velocity - other.velocity).get_entry_exit(other.projection)
+    }
+}
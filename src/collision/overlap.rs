@@ -2,10 +2,15 @@
 ** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
 \*========================================================================*/
 
-use super::Projection;
+use crate::math::Scalar;
+
+use super::ProjectionOf;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Overlap(pub Projection, pub OverlapCase);
+pub struct OverlapOf<S>(pub ProjectionOf<S>, pub OverlapCase);
+
+/// The overlap between two [super::Projection]s
+pub type Overlap = OverlapOf<f32>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum OverlapCase {
@@ -14,37 +19,50 @@ pub enum OverlapCase {
     Unsigned,
 }
 
-pub enum OverlapOffset {
-    Known(f32),
-    Unsure(f32),
+pub enum OverlapOffsetOf<S> {
+    Known(S),
+    Unsure(S),
 }
 
-impl OverlapOffset {
+/// The signed offset of an [Overlap]
+pub type OverlapOffset = OverlapOffsetOf<f32>;
+
+impl<S: Scalar> OverlapOffsetOf<S> {
 
-    pub fn unwrap_or(self, value: f32) -> f32 {
+    pub fn unwrap_or(self, value: S) -> S {
         match self {
             Self::Known(v)  => v,
             Self::Unsure(_) => value,
         }
     }
 
-    pub fn unwrap_or_else<F: FnOnce(f32) -> f32>(self, func: F) -> f32 {
+    pub fn unwrap_or_else<F: FnOnce(S) -> S>(self, func: F) -> S {
         match self {
             Self::Known(v)  => v,
             Self::Unsure(v) => func(v),
         }
     }
 
-    pub fn unwrap_or_sign(self, sign: f32) -> f32 {
+    pub fn unwrap_or_sign(self, sign: S) -> S {
         match self {
             Self::Known(v)  => v,
             Self::Unsure(v) => v*sign,
         }
     }
 
+    /// Unwraps the offset, pushing unsure offsets against the motion along
+    /// the axis so the most recent movement is undone. Positive if not moving.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `motion` The velocity or change in position along the axis
+    pub fn unwrap_or_against(self, motion: S) -> S {
+        self.unwrap_or_sign(if motion > S::zero() { -S::one() } else { S::one() })
+    }
+
 }
 
-impl Overlap {
+impl<S: Scalar> OverlapOf<S> {
     pub fn negate(self) -> Self {
         match self.1 {
             OverlapCase::Positive => Self(self.0, OverlapCase::Negative),
@@ -58,28 +76,28 @@ impl Overlap {
     }
 }
 
-impl Overlap {
+impl<S: Scalar> OverlapOf<S> {
 
-    pub fn length(self) -> f32 {
+    pub fn length(self) -> S {
         self.0.length()
     }
 
 }
 
-impl Overlap {
+impl<S: Scalar> OverlapOf<S> {
 
-    pub fn offset(self) -> OverlapOffset {
+    pub fn offset(self) -> OverlapOffsetOf<S> {
         match self.1 {
-            OverlapCase::Positive =>  OverlapOffset::Known( self.0.length()),
-            OverlapCase::Negative =>  OverlapOffset::Known(-self.0.length()),
-            OverlapCase::Unsigned => OverlapOffset::Unsure( self.0.length()),
+            OverlapCase::Positive =>  OverlapOffsetOf::Known( self.0.length()),
+            OverlapCase::Negative =>  OverlapOffsetOf::Known(-self.0.length()),
+            OverlapCase::Unsigned => OverlapOffsetOf::Unsure( self.0.length()),
         }
     }
 
 }
 
-impl Overlap {
-    pub fn projection(&self) -> &Projection {
+impl<S: Scalar> OverlapOf<S> {
+    pub fn projection(&self) -> &ProjectionOf<S> {
         &self.0
     }
 
@@ -89,8 +107,8 @@ impl Overlap {
 }
 
 
-impl Overlap {
-    pub fn into_tuple(self) -> (Projection, OverlapCase) {
+impl<S: Scalar> OverlapOf<S> {
+    pub fn into_tuple(self) -> (ProjectionOf<S>, OverlapCase) {
         (self.0, self.1)
     }
 }
\ No newline at end of file
@@ -2,15 +2,25 @@
 ** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
 \*========================================================================*/
 
-use super::{Overlap, OverlapCase};
+use arrayvec::ArrayVec;
+
+use crate::math::Scalar;
+
+use super::{OverlapOf, OverlapCase};
 
 /// Represents the minimum and maximum points of a
 /// shape projected onto a particular axis. This is
 /// useful for SAT-based collision detection.
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Projection(f32, f32);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "super::shape_serde::ProjectionRepr<S>", into = "super::shape_serde::ProjectionRepr<S>"))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "S: Scalar + serde::Serialize", deserialize = "S: Scalar + serde::Deserialize<'de>")))]
+pub struct ProjectionOf<S>(S, S);
 
-impl Projection {
+/// A [ProjectionOf] using `f32`
+pub type Projection = ProjectionOf<f32>;
+
+impl<S: Scalar> ProjectionOf<S> {
     /// Construct a new projection, without checking the argument
     /// order - in release builds.
     /// 
@@ -18,7 +28,7 @@ impl Projection {
     /// 
     /// * `near` The dot product of a point along a normal, must be <= `far`
     /// * `far` The dot product of a point along a normal, must be >= `near`
-    pub fn new_unchecked(near: f32, far: f32) -> Self {
+    pub fn new_unchecked(near: S, far: S) -> Self {
         debug_assert!(near <= far, "Attempt to construct backwards projection");
         Self(near, far)
     }
@@ -30,7 +40,7 @@ impl Projection {
     /// 
     /// * `a` The dot product of a point along a normal
     /// * `b` The dot product of a point along a normal
-    pub fn new(a: f32, b: f32) -> Self {
+    pub fn new(a: S, b: S) -> Self {
         if a <= b {
             Self::new_unchecked(a, b)
         } else {
@@ -44,7 +54,7 @@ impl Projection {
     /// 
     /// * `near` The dot product of a point along a normal, returns None if > `far`
     /// * `far` The dot product of a point along a normal, returns None if < `near`
-    pub fn try_new(near: f32, far: f32) -> Option<Self> {
+    pub fn try_new(near: S, far: S) -> Option<Self> {
         if near > far {
             None
         } else {
@@ -53,14 +63,14 @@ impl Projection {
     }
 }
 
-impl Projection {
+impl<S: Scalar> ProjectionOf<S> {
     /// Construct a new projection, from a projected origin point, assigning it
     /// to both `near` and `far`.
     /// 
     /// # Arguments
     /// 
     /// * `origin` The dot product of a point along a normal
-    pub fn point(origin: f32) -> Self {
+    pub fn point(origin: S) -> Self {
         Self::new_unchecked(origin, origin)
     }
 
@@ -71,7 +81,7 @@ impl Projection {
     /// 
     /// * `origin` The dot product of a point along a normal
     /// * `dist` The distance to offset the max from the origin
-    pub fn extent_from(origin: f32, dist: f32) -> Self {
+    pub fn extent_from(origin: S, dist: S) -> Self {
         Self::new_unchecked(origin, origin + dist)
     }
 
@@ -82,25 +92,25 @@ impl Projection {
     /// 
     /// * `origin` The dot product of a point along a normal
     /// * `dist` The distance to offset the `near` and `far` from the origin symmetrically
-    pub fn symmetrical(origin: f32, dist: f32) -> Self {
+    pub fn symmetrical(origin: S, dist: S) -> Self {
         Self::new_unchecked(origin - dist, origin + dist)
     }
 
     /// Creates a projection that covers both of the projections given
     pub fn covering_both(a: Self, b: Self) -> Self {
-        Self::new_unchecked(a.0.min(b.0), b.0.max(b.1))
+        Self::new_unchecked(a.0.min(b.0), a.1.max(b.1))
     }
 
     /// Creates a projection that covers both the given projection and point
-    pub fn covering_point(a: Self, b: f32) -> Self {
+    pub fn covering_point(a: Self, b: S) -> Self {
         Self::new_unchecked(a.0.min(b), a.1.max(b))
     }
 
     /// Smears a projection by offsetting near/far based on the sign of b.
     /// This is only useful for specific situations and therefore isn't suitable
     /// for sweeps.
-    pub fn smear(a: Self, b: f32) -> Self {
-        if b < 0.0 {
+    pub fn smear(a: Self, b: S) -> Self {
+        if b < S::zero() {
             Self::new_unchecked(a.0 + b, a.1)
         } else {
             Self::new_unchecked(a.0, a.1 + b)
@@ -108,34 +118,34 @@ impl Projection {
     }
 }
 
-impl Projection {
+impl<S: Scalar> ProjectionOf<S> {
     /// Gets the minimum value of the projection
-    pub fn near(self) -> f32 {
+    pub fn near(self) -> S {
         self.0
     }
 
     /// Gets the maximum value of the projection
-    pub fn far(self) -> f32 {
+    pub fn far(self) -> S {
         self.1
     }
 
     /// Gets the length of the projection
-    pub fn length(self) -> f32 {
+    pub fn length(self) -> S {
         self.1 - self.0
     }
 
     /// The midpoint of the projection
-    pub fn mid(self) -> f32 {
-        self.length()/2.0 + self.near()
+    pub fn mid(self) -> S {
+        self.length().half() + self.near()
     }
 
     /// Converts to a regular tuple (near, far)
-    pub fn as_tuple(self) -> (f32, f32) {
+    pub fn as_tuple(self) -> (S, S) {
         (self.0, self.1)
     }
 }
 
-impl Projection {
+impl<S: Scalar> ProjectionOf<S> {
     /// Checks if a projection overlaps another one, including exact contact.
     /// 
     /// # Arguments
@@ -146,7 +156,104 @@ impl Projection {
     }
 }
 
-impl Projection {
+impl<S: Scalar> ProjectionOf<S> {
+    /// Checks if a value lies within the projection, including its bounds
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` The value to check
+    pub fn contains_point(self, value: S) -> bool {
+        self.0 <= value && value <= self.1
+    }
+
+    /// Checks if another projection lies entirely within this one, including
+    /// when they share bounds.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` The projection to check
+    pub fn contains(self, other: Self) -> bool {
+        self.0 <= other.0 && other.1 <= self.1
+    }
+
+    /// Calculates the gap between two projections, zero if they overlap or touch
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` The projection to measure the distance to
+    pub fn distance_to(self, other: Self) -> S {
+        (other.0 - self.1).max(self.0 - other.1).max(S::zero())
+    }
+
+    /// Clamps a value so it lies within the projection
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` The value to clamp
+    pub fn clamp(self, value: S) -> S {
+        value.max(self.0).min(self.1)
+    }
+}
+
+impl<S: Scalar> ProjectionOf<S> {
+    /// Grows the projection by a margin on both sides. Negative margins shrink
+    /// it, collapsing to the midpoint rather than reversing.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `margin` The distance to move each bound outwards
+    pub fn expand(self, margin: S) -> Self {
+        if self.length() + margin + margin < S::zero() {
+            Self::point(self.mid())
+        } else {
+            Self::new_unchecked(self.0 - margin, self.1 + margin)
+        }
+    }
+
+    /// Calculates the range covered by both projections, touching projections
+    /// intersect at a single point.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` The projection to intersect with
+    pub fn intersection(self, other: Self) -> Option<Self> {
+        Self::try_new(self.0.max(other.0), self.1.min(other.1))
+    }
+
+    /// Calculates the range covered by either projection, which is a single
+    /// projection if they overlap or touch, otherwise both sorted by `near`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` The projection to unite with
+    pub fn union(self, other: Self) -> ArrayVec<Self, 2> {
+        let mut result = ArrayVec::new_const();
+        if !self.are_separate(other) {
+            result.push(Self::covering_both(self, other));
+        } else if self.0 < other.0 {
+            result.extend([self, other]);
+        } else {
+            result.extend([other, self]);
+        }
+        result
+    }
+
+    /// Splits the projection in two at a value, returning the parts below and
+    /// above it. A part is `None` if the value lies beyond that side, and both
+    /// parts include the value itself.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` The value to split at
+    pub fn split_at(self, value: S) -> (Option<Self>, Option<Self>) {
+        (
+            Self::try_new(self.0, value.min(self.1)),
+            Self::try_new(value.max(self.0), self.1),
+        )
+    }
+}
+
+impl<S: Scalar> ProjectionOf<S> {
     /// Attempts to calculate both of the signed penetrations between two shapes, 
     /// returning overlaps with the correct sign to put the two objects in contact
     /// when `self` is moved along the axis by the length of the overlap.
@@ -157,16 +264,16 @@ impl Projection {
     /// # Arguments
     /// 
     /// * `other` The projection to calculate penetrations with
-    pub fn get_penetration_all(self, other: Self) -> Option<(Overlap, Overlap)> {
+    pub fn get_penetration_all(self, other: Self) -> Option<(OverlapOf<S>, OverlapOf<S>)> {
         match (self.are_separate(other), self.0 <= other.0) {
             (true,     _) => None,
             (false, true) => Some((
-                Overlap(Self::new_unchecked( self.0, other.1), OverlapCase::Positive),
-                Overlap(Self::new_unchecked(other.0,  self.1), OverlapCase::Negative),
+                OverlapOf(Self::new_unchecked( self.0, other.1), OverlapCase::Positive),
+                OverlapOf(Self::new_unchecked(other.0,  self.1), OverlapCase::Negative),
             )),
             (false, false) => Some((
-                Overlap(Self::new_unchecked(other.0,  self.1), OverlapCase::Negative),
-                Overlap(Self::new_unchecked( self.0, other.1), OverlapCase::Positive),
+                OverlapOf(Self::new_unchecked(other.0,  self.1), OverlapCase::Negative),
+                OverlapOf(Self::new_unchecked( self.0, other.1), OverlapCase::Positive),
             )),
         }
     }
@@ -181,7 +288,7 @@ impl Projection {
     /// # Arguments
     /// 
     /// * `other` The projection to calculate the penetration into
-    pub fn get_penetration(self, other: Self) -> Option<Overlap> {
+    pub fn get_penetration(self, other: Self) -> Option<OverlapOf<S>> {
         self.get_penetration_all(other).map(|(near, far)| {
             if near.projection() == far.projection() {
                 near.with_case(OverlapCase::Unsigned)
@@ -192,9 +299,24 @@ impl Projection {
             }
         })
     }
+
+    /// Like [Self::get_penetration], but resolves coinciding projections by
+    /// pushing against the motion instead of returning [OverlapCase::Unsigned].
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` The projection to calculate the penetration into
+    /// * `motion` The velocity or change in position of `self` along the axis
+    pub fn get_penetration_hinted(self, other: Self, motion: S) -> Option<OverlapOf<S>> {
+        self.get_penetration(other).map(|overlap| match (overlap.case(), motion > S::zero()) {
+            (OverlapCase::Unsigned, true ) => overlap.with_case(OverlapCase::Negative),
+            (OverlapCase::Unsigned, false) => overlap.with_case(OverlapCase::Positive),
+            _                              => overlap,
+        })
+    }
 }
 
-impl Projection {
+impl<S: Scalar> ProjectionOf<S> {
     /// Attempts to calculate the signed gap between two shapes, returning an overlap
     /// with the correct sign to put the two objects in contact when `self` is moved
     /// along the axis by the length of the overlap.
@@ -202,11 +324,11 @@ impl Projection {
     /// # Arguments
     /// 
     /// * `other` The projection to calculate the separation from
-    pub fn get_separation(self, other: Self) -> Option<Overlap> {
+    pub fn get_separation(self, other: Self) -> Option<OverlapOf<S>> {
         if self.1 < other.0 {
-            Some(Overlap(Self::new_unchecked( self.1, other.0), OverlapCase::Positive)) 
+            Some(OverlapOf(Self::new_unchecked( self.1, other.0), OverlapCase::Positive)) 
         } else if self.0 > other.1 {
-            Some(Overlap(Self::new_unchecked(other.1,  self.0), OverlapCase::Negative))
+            Some(OverlapOf(Self::new_unchecked(other.1,  self.0), OverlapCase::Negative))
         } else {
             None
         }
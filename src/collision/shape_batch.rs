@@ -0,0 +1,284 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use std::iter::FromIterator;
+
+use crate::prelude::*;
+use super::{Shape, ShapeKind, Projection};
+
+/// Many shapes stored as a structure of arrays, so they can all be projected
+/// onto an axis in one pass. Shapes are grouped by how they project, and each
+/// group has its own branch-free loop, allowing the compiler to vectorize them.
+///
+/// Ellipses store their radii, rectangles their half-size, and triangles their
+/// corners relative to the origin.
+///
+/// Only `f32` is supported, as batching only pays off once vectorized. Fixed
+/// point multiplication saturates through 128-bit intermediates, which doesn't
+/// vectorize, so [super::ShapeOf::project_on] is just as fast for other scalars.
+#[derive(Debug, Default, Clone)]
+pub struct ShapeBatch {
+    len:        usize,
+    ellipses:   ShapeColumns<2>,
+    rectangles: ShapeColumns<2>,
+    triangles:  ShapeColumns<6>,
+}
+
+impl ShapeBatch {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.ellipses.clear();
+        self.rectangles.clear();
+        self.triangles.clear();
+    }
+
+    pub fn push(&mut self, shape: Shape) {
+        let slot = self.len;
+        self.len += 1;
+        match shape.shape {
+            ShapeKind::Ellipse   => self.ellipses  .push(slot, shape.origin, [shape.hbound.x, shape.hbound.y]),
+            ShapeKind::Rectangle => self.rectangles.push(slot, shape.origin, [shape.hbound.x, shape.hbound.y]),
+            _ => {
+                let [a, b, c] = shape.get_points_slope().map(|point| point - shape.origin);
+                self.triangles.push(slot, shape.origin, [a.x, a.y, b.x, b.y, c.x, c.y]);
+            },
+        }
+    }
+
+    /// Projects every shape onto the axis, in the order they were added.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` The normalized axis to project onto
+    /// * `out` Overwritten with the projections
+    pub fn project_on(&self, axis: Vec2, out: &mut ProjectionBatch) {
+        out.near.resize(self.len, 0.0);
+        out.far .resize(self.len, 0.0);
+
+        let (ox, oy, [rx, ry]) = self.ellipses.columns();
+        let (near, far) = out.group(&self.ellipses.slot);
+        for i in 0..near.len() {
+            (near[i], far[i]) = project_ellipse((ox[i], oy[i]), (rx[i], ry[i]), axis);
+        }
+        out.scatter(&self.ellipses.slot);
+
+        let (ox, oy, [hx, hy]) = self.rectangles.columns();
+        let (near, far) = out.group(&self.rectangles.slot);
+        for i in 0..near.len() {
+            (near[i], far[i]) = project_rectangle((ox[i], oy[i]), (hx[i], hy[i]), axis);
+        }
+        out.scatter(&self.rectangles.slot);
+
+        let (ox, oy, [ax, ay, bx, by, cx, cy]) = self.triangles.columns();
+        let (near, far) = out.group(&self.triangles.slot);
+        for i in 0..near.len() {
+            let corners = [(ax[i], ay[i]), (bx[i], by[i]), (cx[i], cy[i])];
+            (near[i], far[i]) = project_triangle((ox[i], oy[i]), corners, axis);
+        }
+        out.scatter(&self.triangles.slot);
+    }
+}
+
+impl FromIterator<Shape> for ShapeBatch {
+    fn from_iter<I: IntoIterator<Item = Shape>>(iter: I) -> Self {
+        let mut result = Self::new();
+        result.extend(iter);
+        result
+    }
+}
+
+impl Extend<Shape> for ShapeBatch {
+    fn extend<I: IntoIterator<Item = Shape>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|shape| self.push(shape));
+    }
+}
+
+/// The shapes of one group in a [ShapeBatch]. Each has its index within the
+/// batch, its origin, and `N` values describing it relative to the origin.
+#[derive(Debug, Clone)]
+struct ShapeColumns<const N: usize> {
+    slot:     Vec<usize>,
+    origin_x: Vec<f32>,
+    origin_y: Vec<f32>,
+    values:   [Vec<f32>; N],
+}
+
+impl<const N: usize> Default for ShapeColumns<N> {
+    fn default() -> Self {
+        Self{
+            slot:     Default::default(),
+            origin_x: Default::default(),
+            origin_y: Default::default(),
+            values:   [(); N].map(|_| Default::default()),
+        }
+    }
+}
+
+impl<const N: usize> ShapeColumns<N> {
+    fn push(&mut self, slot: usize, origin: Vec2, values: [f32; N]) {
+        self.slot.push(slot);
+        self.origin_x.push(origin.x);
+        self.origin_y.push(origin.y);
+        self.values.iter_mut().zip(values).for_each(|(column, value)| column.push(value));
+    }
+
+    fn clear(&mut self) {
+        self.slot.clear();
+        self.origin_x.clear();
+        self.origin_y.clear();
+        self.values.iter_mut().for_each(Vec::clear);
+    }
+
+    /// Every column sliced to the same length, so indexing them in a loop
+    /// bounded by that length needs no bounds checks
+    fn columns(&self) -> (&[f32], &[f32], [&[f32]; N]) {
+        let len = self.slot.len();
+        (&self.origin_x[..len], &self.origin_y[..len], self.values.each_ref().map(|column| &column[..len]))
+    }
+}
+
+/// The result of projecting many shapes, or one shape onto many axes, stored
+/// as a structure of arrays. See [ShapeBatch::project_on] and [project_on_axes].
+#[derive(Debug, Default, Clone)]
+pub struct ProjectionBatch {
+    near: Vec<f32>,
+    far:  Vec<f32>,
+    scratch_near: Vec<f32>,
+    scratch_far:  Vec<f32>,
+}
+
+impl PartialEq for ProjectionBatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.near == other.near && self.far == other.far
+    }
+}
+
+impl ProjectionBatch {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.near.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.near.is_empty()
+    }
+
+    pub fn near(&self) -> &[f32] {
+        &self.near
+    }
+
+    pub fn far(&self) -> &[f32] {
+        &self.far
+    }
+
+    pub fn get(&self, index: usize) -> Option<Projection> {
+        Some(Projection::new_unchecked(*self.near.get(index)?, self.far[index]))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Projection> + '_ {
+        self.near.iter().zip(&self.far).map(|(&near, &far)| Projection::new_unchecked(near, far))
+    }
+
+    /// Where to write the projections of a group in a [ShapeBatch]. Groups
+    /// added in one run are written in place, others go through scratch space
+    /// and must be passed to [Self::scatter] afterwards.
+    fn group(&mut self, slot: &[usize]) -> (&mut [f32], &mut [f32]) {
+        let len = slot.len();
+        match (slot.first(), is_run(slot)) {
+            (Some(&start), true) => (&mut self.near[start..start + len], &mut self.far[start..start + len]),
+            _ => {
+                self.scratch_near.resize(len, 0.0);
+                self.scratch_far .resize(len, 0.0);
+                (&mut self.scratch_near[..len], &mut self.scratch_far[..len])
+            },
+        }
+    }
+
+    /// Moves the projections of a group from scratch space to their slots
+    fn scatter(&mut self, slot: &[usize]) {
+        if is_run(slot) { return; }
+        let (near, far) = (&mut self.near[..], &mut self.far[..]);
+        for ((&slot, &n), &f) in slot.iter().zip(&self.scratch_near).zip(&self.scratch_far) {
+            near[slot] = n;
+            far [slot] = f;
+        }
+    }
+}
+
+/// Checks if ascending slots are consecutive, ie. were added in one run
+fn is_run(slot: &[usize]) -> bool {
+    match (slot.first(), slot.last()) {
+        (Some(first), Some(last)) => last - first + 1 == slot.len(),
+        _ => true,
+    }
+}
+
+/// Projects a shape onto every axis, in order. The counterpart of
+/// [ShapeBatch::project_on], for testing one shape against many axes.
+///
+/// # Arguments
+///
+/// * `shape` The shape to project
+/// * `axes` The normalized axes to project onto
+/// * `out` Overwritten with the projections
+pub fn project_on_axes(shape: &Shape, axes: &[Vec2], out: &mut ProjectionBatch) {
+    out.near.resize(axes.len(), 0.0);
+    out.far .resize(axes.len(), 0.0);
+    let (near, far) = (&mut out.near[..axes.len()], &mut out.far[..axes.len()]);
+
+    let (origin, hbound) = (shape.origin.into_tuple(), shape.hbound.into_tuple());
+    match shape.shape {
+        ShapeKind::Ellipse   => for i in 0..axes.len() {
+            (near[i], far[i]) = project_ellipse(origin, hbound, axes[i]);
+        },
+        ShapeKind::Rectangle => for i in 0..axes.len() {
+            (near[i], far[i]) = project_rectangle(origin, hbound, axes[i]);
+        },
+        _ => {
+            let corners = shape.get_points_slope().map(|point| (point - shape.origin).into_tuple());
+            for i in 0..axes.len() {
+                (near[i], far[i]) = project_triangle(origin, corners, axes[i]);
+            }
+        },
+    }
+}
+
+/// Projects an ellipse, matching [Shape::get_extent_on_axis]
+#[inline(always)]
+fn project_ellipse(origin: (f32, f32), radius: (f32, f32), axis: Vec2) -> (f32, f32) {
+    let (x, y) = (radius.0*axis.x, radius.1*axis.y);
+    let (centre, extent) = (origin.0*axis.x + origin.1*axis.y, (x*x + y*y).sqrt());
+    (centre - extent, centre + extent)
+}
+
+/// Projects a rectangle, which is symmetrical around its origin
+#[inline(always)]
+fn project_rectangle(origin: (f32, f32), hbound: (f32, f32), axis: Vec2) -> (f32, f32) {
+    let extent = (hbound.0*axis.x).abs() + (hbound.1*axis.y).abs();
+    let centre = origin.0*axis.x + origin.1*axis.y;
+    (centre - extent, centre + extent)
+}
+
+/// Projects a triangle from its corners relative to its origin
+#[inline(always)]
+fn project_triangle(origin: (f32, f32), corners: [(f32, f32); 3], axis: Vec2) -> (f32, f32) {
+    let [d0, d1, d2] = corners.map(|(x, y)| x*axis.x + y*axis.y);
+    let centre = origin.0*axis.x + origin.1*axis.y;
+    (centre + d0.min(d1).min(d2), centre + d0.max(d1).max(d2))
+}
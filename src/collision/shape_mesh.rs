@@ -0,0 +1,66 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::render::{
+    mesh::{Indices, Mesh},
+    render_resource::PrimitiveTopology,
+};
+
+use crate::prelude::*;
+use super::{Shape, ShapeKind};
+
+/// The number of segments used for ellipses when converting a [Shape] directly
+pub const SHAPE_MESH_SEGMENTS: usize = 32;
+
+/// Describes a filled mesh matching a [Shape], with vertices relative to the
+/// shape's origin so it can be placed with a transform. UVs span the shape's
+/// bounds, with v increasing downwards.
+#[derive(Debug, Clone, Copy)]
+pub struct ShapeMesh {
+    pub shape: Shape,
+    /// The number of segments used to approximate ellipses, see [Shape::get_points_ellipse]
+    pub segments: usize,
+}
+
+impl From<Shape> for ShapeMesh {
+    fn from(shape: Shape) -> Self {
+        Self{ shape, segments: SHAPE_MESH_SEGMENTS }
+    }
+}
+
+impl From<Shape> for Mesh {
+    fn from(shape: Shape) -> Self {
+        ShapeMesh::from(shape).into()
+    }
+}
+
+impl From<ShapeMesh> for Mesh {
+    fn from(ShapeMesh{ shape, segments }: ShapeMesh) -> Self {
+        // Every outline is counter-clockwise, so triangles face +z
+        let (points, indices): (Vec<Vec2>, Vec<u32>) = match shape.shape {
+            ShapeKind::Rectangle => (shape.get_points_bound().to_vec(), vec![0, 1, 2, 0, 2, 3]),
+            ShapeKind::Ellipse   => {
+                let mut points = vec![shape.origin];
+                points.extend(shape.get_points_ellipse(segments));
+                let indices = (0..segments as u32).flat_map(|i| [0, i + 1, (i + 1) % segments as u32 + 1]).collect();
+                (points, indices)
+            },
+            _ => (shape.get_points_slope().to_vec(), vec![0, 1, 2]),
+        };
+
+        let uv = |value: f32, origin: f32, hbound: f32| if hbound > 0.0 { 0.5 + 0.5*(value - origin)/hbound } else { 0.5 };
+        let positions: Vec<[f32; 3]> = points.iter().map(|p| [p.x - shape.origin.x, p.y - shape.origin.y, 0.0]).collect();
+        let uvs: Vec<[f32; 2]> = points.iter().map(|p| [
+            uv(p.x, shape.origin.x, shape.hbound.x),
+            1.0 - uv(p.y, shape.origin.y, shape.hbound.y),
+        ]).collect();
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; positions.len()]);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+}
@@ -0,0 +1,104 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use std::convert::TryFrom;
+
+use serde::{Serialize, Deserialize};
+
+use crate::math::{Scalar, Vector};
+use super::{ShapeOf, ShapeKind, ProjectionOf};
+
+/// The serialized form of [Shape], mirroring its constructors. Sizes are half
+/// extents, as with [Shape::rectangle].
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Shape")]
+pub(super) enum ShapeRepr<S: Scalar> {
+    Point     { origin: S::Vec2 },
+    Circle    { origin: S::Vec2, radius: S },
+    Ellipse   { origin: S::Vec2, radius: S::Vec2 },
+    Square    { origin: S::Vec2, size: S },
+    Rectangle { origin: S::Vec2, size: S::Vec2 },
+    Triangle  { origin: S::Vec2, size: S::Vec2, incline: bool, invert: bool },
+}
+
+impl<S: Scalar> From<ShapeOf<S>> for ShapeRepr<S> {
+    fn from(ShapeOf{ origin, hbound, shape }: ShapeOf<S>) -> Self {
+        match ShapeKindRepr::from(shape) {
+            ShapeKindRepr::Ellipse if hbound == S::Vec2::ZERO      => Self::Point{ origin },
+            ShapeKindRepr::Ellipse if hbound.x() == hbound.y()   => Self::Circle{ origin, radius: hbound.x() },
+            ShapeKindRepr::Ellipse                               => Self::Ellipse{ origin, radius: hbound },
+            ShapeKindRepr::Rectangle if hbound.x() == hbound.y() => Self::Square{ origin, size: hbound.x() },
+            ShapeKindRepr::Rectangle                             => Self::Rectangle{ origin, size: hbound },
+            ShapeKindRepr::Triangle{ incline, invert }           => Self::Triangle{ origin, size: hbound, incline, invert },
+        }
+    }
+}
+
+impl<S: Scalar> From<ShapeRepr<S>> for ShapeOf<S> {
+    fn from(repr: ShapeRepr<S>) -> Self {
+        match repr {
+            ShapeRepr::Point{ origin }                            => Self::point(origin),
+            ShapeRepr::Circle{ origin, radius }                   => Self::circle(origin, radius),
+            ShapeRepr::Ellipse{ origin, radius }                  => Self::ellipse(origin, radius),
+            ShapeRepr::Square{ origin, size }                     => Self::square(origin, size),
+            ShapeRepr::Rectangle{ origin, size }                  => Self::rectangle(origin, size),
+            ShapeRepr::Triangle{ origin, size, incline, invert } => Self::triangle(origin, size, incline, invert),
+        }
+    }
+}
+
+/// The serialized form of [ShapeKind], with triangles described the same way
+/// as [Shape::triangle].
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ShapeKind")]
+pub(super) enum ShapeKindRepr {
+    Ellipse,
+    Rectangle,
+    Triangle{ incline: bool, invert: bool },
+}
+
+impl From<ShapeKind> for ShapeKindRepr {
+    fn from(kind: ShapeKind) -> Self {
+        match kind {
+            ShapeKind::Ellipse               => Self::Ellipse,
+            ShapeKind::Rectangle             => Self::Rectangle,
+            ShapeKind::TriangleDeclineNormal => Self::Triangle{ incline: false, invert: false },
+            ShapeKind::TriangleDeclineInvert => Self::Triangle{ incline: false, invert: true  },
+            ShapeKind::TriangleInclineNormal => Self::Triangle{ incline: true,  invert: false },
+            ShapeKind::TriangleInclineInvert => Self::Triangle{ incline: true,  invert: true  },
+        }
+    }
+}
+
+impl From<ShapeKindRepr> for ShapeKind {
+    fn from(repr: ShapeKindRepr) -> Self {
+        match repr {
+            ShapeKindRepr::Ellipse   => ShapeKind::Ellipse,
+            ShapeKindRepr::Rectangle => ShapeKind::Rectangle,
+            ShapeKindRepr::Triangle{ incline, invert } => ShapeKind::triangle(incline, invert),
+        }
+    }
+}
+
+/// The serialized form of [Projection], rejecting reversed bounds
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Projection")]
+pub(super) struct ProjectionRepr<S> {
+    near: S,
+    far:  S,
+}
+
+impl<S: Scalar> From<ProjectionOf<S>> for ProjectionRepr<S> {
+    fn from(projection: ProjectionOf<S>) -> Self {
+        Self{ near: projection.near(), far: projection.far() }
+    }
+}
+
+impl<S: Scalar> TryFrom<ProjectionRepr<S>> for ProjectionOf<S> {
+    type Error = &'static str;
+
+    fn try_from(ProjectionRepr{ near, far }: ProjectionRepr<S>) -> Result<Self, Self::Error> {
+        Self::try_new(near, far).ok_or("projection near must not be greater than far")
+    }
+}
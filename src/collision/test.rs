@@ -1,4 +1,4 @@
-use super::{Overlap, OverlapCase, Projection};
+use super::{Overlap, OverlapOf, OverlapCase, Projection};
 
     struct TestCase(
         &'static str,
@@ -54,21 +54,21 @@ use super::{Overlap, OverlapCase, Projection};
         let touch      = |  at: i32| create(            at,             at);
 
         (create(bound_l, bound_r), [
-            TestCase("gap_l",       create(bound_l - 2, bound_l - 1), Some(Overlap(gap_from_l(1), OverlapCase::Negative)), None,                                                true),       
-            TestCase("gap_r",       create(bound_r + 1, bound_r + 2), Some(Overlap(gap_from_r(1), OverlapCase::Positive )), None,                                                true),
-            TestCase("touch_l",     create(bound_l - 2, bound_l    ), None,                                                Some(Overlap(     touch(bound_l  ), OverlapCase::Positive )), true),     
-            TestCase("touch_r",     create(bound_r,     bound_r + 2), None,                                                Some(Overlap(     touch(bound_r  ), OverlapCase::Negative)), true),
-            TestCase("penetrate_l", create(bound_l - 2, bound_l + 1), None,                                                Some(Overlap(pen_from_l(1        ), OverlapCase::Positive )), true), 
-            TestCase("penetrate_r", create(bound_r - 1, bound_r + 2), None,                                                Some(Overlap(pen_from_r(1        ), OverlapCase::Negative)), true),
-            TestCase("contained_l", create(bound_l + 1, bound_r - 2), None,                                                Some(Overlap(pen_from_l(WIDTH - 2), OverlapCase::Positive )), true), 
-            TestCase("contained_r", create(bound_l + 2, bound_r - 1), None,                                                Some(Overlap(pen_from_r(WIDTH - 2), OverlapCase::Negative)), true), 
-            TestCase("contained_m", create(bound_l + 1, bound_r - 1), None,                                                Some(Overlap(pen_from_l(WIDTH - 1), OverlapCase::Positive )), true),
-            TestCase("contains_l",  create(bound_l - 2, bound_r + 1), None,                                                Some(Overlap(pen_from_l(WIDTH + 1), OverlapCase::Positive )), true),  
-            TestCase("contains_r",  create(bound_l - 1, bound_r + 2), None,                                                Some(Overlap(pen_from_r(WIDTH + 1), OverlapCase::Negative)), true),  
-            TestCase("contains_m",  create(bound_l - 1, bound_r + 1), None,                                                Some(Overlap(pen_from_r(WIDTH + 1), OverlapCase::Negative)), true),
-            TestCase("overlap_l",   create(bound_l - 1, bound_r    ), None,                                                Some(Overlap(pen_from_l(WIDTH    ), OverlapCase::Positive )), true),   
-            TestCase("overlap_r",   create(bound_l,     bound_r + 1), None,                                                Some(Overlap(pen_from_r(WIDTH    ), OverlapCase::Negative)), true),   
-            TestCase("overlap_m",   create(bound_l,     bound_r    ), None,                                                Some(Overlap(pen_from_l(WIDTH    ), OverlapCase::Unsigned )), true),
+            TestCase("gap_l",       create(bound_l - 2, bound_l - 1), Some(OverlapOf(gap_from_l(1), OverlapCase::Negative)), None,                                                true),       
+            TestCase("gap_r",       create(bound_r + 1, bound_r + 2), Some(OverlapOf(gap_from_r(1), OverlapCase::Positive )), None,                                                true),
+            TestCase("touch_l",     create(bound_l - 2, bound_l    ), None,                                                Some(OverlapOf(     touch(bound_l  ), OverlapCase::Positive )), true),     
+            TestCase("touch_r",     create(bound_r,     bound_r + 2), None,                                                Some(OverlapOf(     touch(bound_r  ), OverlapCase::Negative)), true),
+            TestCase("penetrate_l", create(bound_l - 2, bound_l + 1), None,                                                Some(OverlapOf(pen_from_l(1        ), OverlapCase::Positive )), true), 
+            TestCase("penetrate_r", create(bound_r - 1, bound_r + 2), None,                                                Some(OverlapOf(pen_from_r(1        ), OverlapCase::Negative)), true),
+            TestCase("contained_l", create(bound_l + 1, bound_r - 2), None,                                                Some(OverlapOf(pen_from_l(WIDTH - 2), OverlapCase::Positive )), true), 
+            TestCase("contained_r", create(bound_l + 2, bound_r - 1), None,                                                Some(OverlapOf(pen_from_r(WIDTH - 2), OverlapCase::Negative)), true), 
+            TestCase("contained_m", create(bound_l + 1, bound_r - 1), None,                                                Some(OverlapOf(pen_from_l(WIDTH - 1), OverlapCase::Positive )), true),
+            TestCase("contains_l",  create(bound_l - 2, bound_r + 1), None,                                                Some(OverlapOf(pen_from_l(WIDTH + 1), OverlapCase::Positive )), true),  
+            TestCase("contains_r",  create(bound_l - 1, bound_r + 2), None,                                                Some(OverlapOf(pen_from_r(WIDTH + 1), OverlapCase::Negative)), true),  
+            TestCase("contains_m",  create(bound_l - 1, bound_r + 1), None,                                                Some(OverlapOf(pen_from_r(WIDTH + 1), OverlapCase::Negative)), true),
+            TestCase("overlap_l",   create(bound_l - 1, bound_r    ), None,                                                Some(OverlapOf(pen_from_l(WIDTH    ), OverlapCase::Positive )), true),   
+            TestCase("overlap_r",   create(bound_l,     bound_r + 1), None,                                                Some(OverlapOf(pen_from_r(WIDTH    ), OverlapCase::Negative)), true),   
+            TestCase("overlap_m",   create(bound_l,     bound_r    ), None,                                                Some(OverlapOf(pen_from_l(WIDTH    ), OverlapCase::Unsigned )), true),
         ])
     }
 
@@ -86,4 +86,483 @@ use super::{Overlap, OverlapCase, Projection};
                 }
             }
         }
-    }
\ No newline at end of file
+    }
+
+    #[test]
+    fn test_covering_both() {
+        let create = |l: f32, r: f32| Projection::new_unchecked(l, r);
+        let cases = [
+            ("a_far_larger",  create(0.0, 5.0), create(1.0, 2.0), create(0.0, 5.0)),
+            ("b_far_larger",  create(1.0, 2.0), create(0.0, 5.0), create(0.0, 5.0)),
+            ("a_before_b",    create(0.0, 1.0), create(3.0, 4.0), create(0.0, 4.0)),
+            ("b_before_a",    create(3.0, 4.0), create(0.0, 1.0), create(0.0, 4.0)),
+            ("overlap_a_far", create(1.0, 6.0), create(0.0, 3.0), create(0.0, 6.0)),
+        ];
+        for (name, a, b, expect) in cases {
+            assert_eq!(Projection::covering_both(a, b), expect, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_interval_algebra() {
+        let (target, cases) = create_cases(0);
+        let create = |l: f32, r: f32| Projection::new_unchecked(l, r);
+        let mut fails = String::new();
+        for case in cases {
+            let (name, other) = (case.0, case.1);
+            let separate = target.are_separate(other);
+
+            // Intersection exists exactly when they don't separate, and is contained by both
+            match target.intersection(other) {
+                Some(i) if separate || !target.contains(i) || !other.contains(i) => {
+                    fails = format!("{}\n- {}: intersection ({:?}) isn't within both", fails, name, i);
+                },
+                None if !separate => fails = format!("{}\n- {}: missing intersection", fails, name),
+                _ => {},
+            }
+            if target.intersection(other) != other.intersection(target) {
+                fails = format!("{}\n- {}: intersection isn't symmetrical", fails, name);
+            }
+
+            // Union is one projection unless separate, and always covers both
+            let union = target.union(other);
+            if union.len() != if separate { 2 } else { 1 } || !union.iter().any(|u| u.contains(target)) || !union.iter().any(|u| u.contains(other)) {
+                fails = format!("{}\n- {}: union ({:?}) doesn't cover both", fails, name, union);
+            }
+
+            // Distance is the separation length, zero when touching or overlapping
+            let distance = case.2.map_or(0.0, |overlap| overlap.length());
+            if target.distance_to(other) != distance || other.distance_to(target) != distance {
+                fails = format!("{}\n- {}: distance ({}) expected ({})", fails, name, target.distance_to(other), distance);
+            }
+        }
+        assert!(fails.is_empty(), "Failed interval tests: {}\n", fails);
+
+        assert!(target.contains(target) && !target.contains(target.expand(1.0)) && target.expand(1.0).contains(target));
+        assert_eq!(target.expand(1.0), create(-1.0, 5.0));
+        assert_eq!(target.expand(-1.0), create(1.0, 3.0));
+        assert_eq!(target.expand(-3.0), Projection::point(2.0));
+
+        assert!(target.contains_point(0.0) && target.contains_point(4.0) && !target.contains_point(4.5));
+        assert_eq!([target.clamp(-1.0), target.clamp(2.5), target.clamp(9.0)], [0.0, 2.5, 4.0]);
+
+        assert_eq!(target.split_at(1.0), (Some(create(0.0, 1.0)), Some(create(1.0, 4.0))));
+        assert_eq!(target.split_at(-1.0), (None, Some(target)));
+        assert_eq!(target.split_at(5.0), (Some(target), None));
+    }
+
+    #[test]
+    fn test_moving_projection() {
+        use super::MovingProjection;
+
+        let target = Projection::new(0.0, 4.0);
+        let moving = |l: f32, r: f32, v: f32| MovingProjection::new(Projection::new(l, r), v);
+
+        assert_eq!(moving(-3.0, -1.0, 2.0).get_entry_exit(target), Some((0.5, 3.5)));
+        assert_eq!(moving(6.0, 7.0, -1.0).get_entry_exit(target), Some((2.0, 7.0)));
+        assert_eq!(moving(1.0, 2.0, 1.0).get_entry_exit(target), Some((-2.0, 3.0)));
+        assert_eq!(moving(1.0, 2.0, 0.0).get_entry_exit(target), Some((f32::NEG_INFINITY, f32::INFINITY)));
+        assert_eq!(moving(5.0, 6.0, 0.0).get_entry_exit(target), None);
+        assert_eq!(moving(-3.0, -1.0, 2.0).at(0.5), Projection::new(-2.0, 0.0));
+
+        // Both moving matches moving relative to a stationary projection
+        let other = MovingProjection::new(target, -1.0);
+        assert_eq!(moving(-3.0, -1.0, 1.0).get_entry_exit_moving(other), Some((0.5, 3.5)));
+    }
+
+    #[test]
+    fn test_time_of_impact() {
+        use crate::math::Vec2;
+        use super::Shape;
+
+        let wall = Shape::rectangle(Vec2::new(5.0, 0.0), Vec2::new(1.0, 5.0));
+        let square = Shape::square(Vec2::ZERO, 1.0);
+        assert_eq!(square.time_of_impact(Vec2::new(6.0, 0.0), &wall), Some(0.5));
+        assert_eq!(square.time_of_impact(Vec2::new(2.0, 0.0), &wall), None);
+        assert_eq!(square.time_of_impact(Vec2::new(-6.0, 0.0), &wall), None);
+        assert_eq!(square.translated(Vec2::new(4.0, 0.0)).time_of_impact(Vec2::X, &wall), Some(0.0));
+
+        // Passing by the corner only touches on the x-axis, but not at the same time as y
+        assert_eq!(square.translated(Vec2::new(0.0, 10.0)).time_of_impact(Vec2::new(8.0, -2.0), &wall), None);
+
+        // Sliding down onto a slope, first touching the diagonal face
+        let slope = Shape::triangle(Vec2::new(0.0, -4.0), Vec2::ONE, true, false);
+        let toi = square.translated(Vec2::new(-0.5, 0.0)).time_of_impact(Vec2::new(0.0, -4.0), &slope).unwrap();
+        assert!((toi - 0.625).abs() < 1e-5, "{}", toi);
+
+        let circle = Shape::circle(Vec2::ZERO, 1.0);
+        let toi = circle.time_of_impact(Vec2::new(10.0, 0.0), &Shape::circle(Vec2::new(6.0, 0.0), 1.0)).unwrap();
+        assert!((toi - 0.4).abs() < 1e-5, "{}", toi);
+
+        // Moving diagonally past the other circle, which the axis from the start can't tell
+        let motion = Vec2::new(20.0, 20.0);
+        assert_eq!(circle.translated(Vec2::new(-10.0, -7.0)).time_of_impact(motion, &circle), None);
+        let toi = circle.translated(Vec2::new(-10.0, -9.0)).time_of_impact(motion, &circle).unwrap();
+        assert!((toi - (760.0 - 11200f32.sqrt())/1600.0).abs() < 1e-3, "{}", toi);
+    }
+
+    #[test]
+    fn test_hinted_penetration() {
+        use crate::math::Vec2;
+        use super::{InternalEdges, Shape};
+
+        let none = InternalEdges::new();
+
+        let a = Projection::new(0.0, 1.0);
+        let case = |motion: f32| a.get_penetration_hinted(a, motion).map(|overlap| *overlap.case());
+        assert_eq!(case( 1.0), Some(OverlapCase::Negative));
+        assert_eq!(case(-1.0), Some(OverlapCase::Positive));
+        assert_eq!(case( 0.0), Some(OverlapCase::Positive));
+        assert_eq!(Projection::new(0.5, 2.0).get_penetration_hinted(a, -1.0), Projection::new(0.5, 2.0).get_penetration(a));
+
+        // Coinciding squares are pushed back the way they came, on any axis
+        let square = Shape::square(Vec2::ZERO, 1.0);
+        let push = |motion: Vec2| square.get_penetration_hinted(&square, &none, motion);
+        assert_eq!(push(Vec2::ZERO),            Some(Vec2::new( 2.0,  0.0)));
+        assert_eq!(push(Vec2::new( 1.0,  0.0)), Some(Vec2::new(-2.0,  0.0)));
+        assert_eq!(push(Vec2::new( 0.0, -2.0)), Some(Vec2::new( 0.0,  2.0)));
+        assert_eq!(push(Vec2::new(-1.0,  3.0)), Some(Vec2::new( 0.0, -2.0)));
+        assert_eq!(square.get_penetration(&square), push(Vec2::ZERO));
+
+        // A clear minimum is kept regardless of the hint
+        let shifted = square.translated(Vec2::new(0.75, 0.0));
+        assert_eq!(shifted.get_penetration_hinted(&square, &none, Vec2::X), Some(Vec2::new(1.25, 0.0)));
+    }
+
+    #[test]
+    fn test_internal_edges() {
+        use crate::math::Vec2;
+        use super::{InternalEdges, Shape, Side};
+
+        // Touching spans merge, separate spans stay sorted
+        let mut edges = InternalEdges::new();
+        edges.add(Side::Left, Projection::new(2.0, 3.0));
+        edges.add(Side::Left, Projection::new(0.0, 0.5));
+        edges.add(Side::Left, Projection::new(1.0, 2.0));
+        assert_eq!(edges.spans(Side::Left), [Projection::new(0.0, 0.5), Projection::new(1.0, 3.0)]);
+        assert!(edges.spans(Side::Right).is_empty() && !edges.is_empty());
+        assert!(edges.contains(Side::Left, Projection::new(1.5, 3.0)));
+        assert!(!edges.contains(Side::Left, Projection::new(0.25, 1.5)));
+
+        // Only contacts entirely within a span are ignored
+        let wall = Shape::rectangle(Vec2::new(1.0, 2.0), Vec2::new(1.0, 2.0));
+        let inside  = Shape::square(Vec2::new(0.1, 2.0), 0.25);
+        let partial = Shape::square(Vec2::new(0.1, 0.6), 0.25);
+        let (push, snag) = (inside.get_penetration_with_edges(&wall, &edges).unwrap(), inside.get_penetration(&wall).unwrap());
+        assert!(push.y == 0.0 && (push.x - 2.15).abs() < 1e-5);
+        assert!(snag.y == 0.0 && (snag.x + 0.35).abs() < 1e-5);
+        assert_eq!(partial.get_penetration_with_edges(&wall, &edges), partial.get_penetration(&wall));
+    }
+
+    #[test]
+    fn test_generic_scalar() {
+        use crate::math::{Fixed, Scalar, Vector};
+        use super::{InternalEdgesOf, ShapeOf};
+
+        // Runs the same tests with any scalar, returning results as f32
+        fn results<S: Scalar>() -> Vec<Option<(f32, f32)>> {
+            let v = |x: f32, y: f32| S::Vec2::new(S::from_f32(x), S::from_f32(y));
+            let tuple = |v: S::Vec2| (v.x().to_f32(), v.y().to_f32());
+
+            let square = ShapeOf::<S>::square(v(0.0, 0.0), S::one());
+            let others = [
+                ShapeOf::<S>::rectangle(v(1.5, 0.25), v(1.0, 2.0)),
+                ShapeOf::<S>::triangle(v(0.5, -1.5), v(1.0, 1.0), true, false),
+                ShapeOf::<S>::circle(v(-1.0, 1.5), S::from_f32(1.0)),
+                ShapeOf::<S>::ellipse(v(1.0, 1.5), v(0.5, 1.0)),
+                ShapeOf::<S>::square(v(5.0, 0.0), S::one()),
+            ];
+
+            let mut result = Vec::new();
+            for other in others {
+                result.push(square.get_penetration(&other).map(tuple));
+                result.push(square.get_penetration_hinted(&other, &InternalEdgesOf::new(), v(1.0, 0.0)).map(tuple));
+                result.push(square.time_of_impact(v(8.0, -2.0), &other).map(|t| (t.to_f32(), 0.0)));
+            }
+            result
+        }
+
+        let (expect, doubles, fixed) = (results::<f32>(), results::<f64>(), results::<Fixed>());
+        assert!(expect.iter().any(Option::is_some) && expect.iter().any(Option::is_none));
+        for (index, ((a, b), c)) in expect.iter().zip(doubles.iter()).zip(fixed.iter()).enumerate() {
+            let close = |other: &Option<(f32, f32)>| match (a, other) {
+                (Some(a), Some(b)) => (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4,
+                (a, b) => a.is_none() && b.is_none(),
+            };
+            assert!(close(b) && close(c), "case {}: {:?} {:?} {:?}", index, a, b, c);
+        }
+    }
+
+    #[test]
+    fn test_shape_batch() {
+        use crate::math::Vec2;
+        use super::{ProjectionBatch, Shape, ShapeBatch, project_on_axes};
+
+        // Kinds are interleaved, so each group is spread through the batch
+        let shapes = [
+            Shape::point(Vec2::new(-3.0, 1.0)),
+            Shape::circle(Vec2::new(2.0, -1.5), 1.5),
+            Shape::ellipse(Vec2::new(-1.25, 0.5), Vec2::new(2.0, 0.75)),
+            Shape::rectangle(Vec2::new(4.0, 2.0), Vec2::new(0.5, 1.25)),
+            Shape::triangle(Vec2::new(-6.0, -2.0), Vec2::new(1.0, 2.0), false, false),
+            Shape::triangle(Vec2::new(0.5, 3.0), Vec2::new(1.5, 0.5), true, false),
+            Shape::ellipse(Vec2::new(7.5, -4.0), Vec2::new(0.25, 1.5)),
+            Shape::triangle(Vec2::new(-2.5, -5.0), Vec2::new(0.75, 0.75), false, true),
+            Shape::rectangle(Vec2::new(-8.0, 4.5), Vec2::new(3.0, 0.25)),
+            Shape::triangle(Vec2::new(5.5, 6.0), Vec2::new(2.5, 1.0), true, true),
+            Shape::circle(Vec2::new(-4.5, -7.0), 0.5),
+        ];
+        let axes: Vec<Vec2> = (0..16).map(|index| Vec2::new((index as f32*0.4).cos(), (index as f32*0.4).sin())).collect();
+
+        let close = |a: Projection, b: Projection| (a.near() - b.near()).abs() < 1e-4 && (a.far() - b.far()).abs() < 1e-4;
+        let batch: ShapeBatch = shapes.iter().copied().collect();
+        let mut out = ProjectionBatch::new();
+        assert_eq!(batch.len(), shapes.len());
+
+        for &axis in &axes {
+            batch.project_on(axis, &mut out);
+            assert_eq!(out.len(), shapes.len());
+            for (shape, projection) in shapes.iter().zip(out.iter()) {
+                assert!(close(shape.project_on(axis), projection), "{:?} on {:?}", shape, axis);
+            }
+        }
+
+        // Shapes added in runs of one kind are written in place rather than reordered
+        let mut runs = shapes.to_vec();
+        runs.sort_by_key(|shape| shape.shape as usize);
+        let batch: ShapeBatch = runs.iter().copied().collect();
+        for &axis in &axes {
+            batch.project_on(axis, &mut out);
+            for (shape, projection) in runs.iter().zip(out.iter()) {
+                assert!(close(shape.project_on(axis), projection), "{:?} on {:?}", shape, axis);
+            }
+        }
+
+        for shape in &shapes {
+            project_on_axes(shape, &axes, &mut out);
+            assert_eq!(out.len(), axes.len());
+            for (&axis, projection) in axes.iter().zip(out.iter()) {
+                assert!(close(shape.project_on(axis), projection), "{:?} on {:?}", shape, axis);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ellipse_projection() {
+        use crate::math::Vec2;
+        use super::Shape;
+
+        let ellipse = Shape::ellipse(Vec2::ONE, Vec2::new(2.0, 1.0));
+        let diagonal = Vec2::ONE.normalize();
+        let extent = ellipse.project_on(diagonal).far() - diagonal.dot(ellipse.origin);
+        assert!((extent - 2.5f32.sqrt()).abs() < 1e-5, "{}", extent);
+        assert!((ellipse.get_radius_on_axis(diagonal) - 2.0/2.5f32.sqrt()).abs() < 1e-5);
+
+        // Points stay on the edge, rather than at the projected extent
+        for point in ellipse.get_points_ellipse(16) {
+            let local = (point - ellipse.origin)/ellipse.hbound;
+            assert!((local.length() - 1.0).abs() < 1e-5, "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn test_triangle_penetration() {
+        use crate::math::Vec2;
+        use super::Shape;
+
+        // The slope of a tall triangle is steeper than the diagonal
+        let slope = Vec2::new(2.0, 1.0).normalize();
+        let cases = [
+            (false, false, Vec2::new( 1.0,  1.0)),
+            ( true, false, Vec2::new(-1.0,  1.0)),
+            (false,  true, Vec2::new(-1.0, -1.0)),
+            ( true,  true, Vec2::new( 1.0, -1.0)),
+        ];
+        for (incline, invert, sign) in cases {
+            let triangle = Shape::triangle(Vec2::ZERO, Vec2::new(1.0, 2.0), incline, invert);
+            let push = Shape::point(sign*-0.1).get_penetration(&triangle).unwrap();
+            assert!((push - sign*slope*0.3/5f32.sqrt()).length() < 1e-5, "{:?} {:?}", triangle, push);
+        }
+    }
+
+    #[test]
+    fn test_ellipse_penetration() {
+        use crate::math::Vec2;
+        use super::{Shape, ShapeKind};
+
+        // Dense polygon approximation, with SAT over every edge normal
+        fn polygon(shape: &Shape) -> Vec<Vec2> {
+            match shape.shape {
+                ShapeKind::Ellipse   => shape.get_points_ellipse(512),
+                ShapeKind::Rectangle => shape.get_points_bound().to_vec(),
+                _                    => shape.get_points_slope().to_vec(),
+            }
+        }
+
+        fn polygon_depth(a: &[Vec2], b: &[Vec2]) -> f32 {
+            let project = |points: &[Vec2], axis: Vec2| points.iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY), 
+                |(near, far), point| (near.min(axis.dot(*point)), far.max(axis.dot(*point)))
+            );
+            let normals = [a, b].into_iter().flat_map(|points| (0..points.len()).filter_map(move |index| {
+                let edge = points[(index + 1) % points.len()] - points[index];
+                Vec2::new(-edge.y, edge.x).try_normalize()
+            }));
+            normals.map(|axis| {
+                let ((a_near, a_far), (b_near, b_far)) = (project(a, axis), project(b, axis));
+                (a_far - b_near).min(b_far - a_near)
+            }).fold(f32::INFINITY, f32::min)
+        }
+
+        let mut seed = 0x2545_f491_u32;
+        let mut random = |low: f32, high: f32| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            low + (high - low)*(seed >> 8) as f32/(1 << 24) as f32
+        };
+
+        // Every kind of shape, placed and sized randomly
+        let kinds: [fn(Vec2, Vec2) -> Shape; 7] = [
+            Shape::ellipse,
+            |origin, size| Shape::circle(origin, size.x),
+            Shape::rectangle,
+            |origin, size| Shape::triangle(origin, size, false, false),
+            |origin, size| Shape::triangle(origin, size, false,  true),
+            |origin, size| Shape::triangle(origin, size,  true, false),
+            |origin, size| Shape::triangle(origin, size,  true,  true),
+        ];
+
+        let (mut checked, mut overlapping) = (0, 0);
+        for index in 0..240 {
+            let ellipse = Shape::ellipse(Vec2::new(random(-1.5, 1.5), random(-1.5, 1.5)), Vec2::new(random(0.1, 2.0), random(0.1, 2.0)));
+            let (origin, size) = (Vec2::new(random(-1.5, 1.5), random(-1.5, 1.5)), Vec2::new(random(0.1, 2.0), random(0.1, 2.0)));
+            let other = kinds[index % kinds.len()](origin, size);
+
+            let depth = polygon_depth(&polygon(&ellipse), &polygon(&other));
+            // Too close to touching for the approximation to decide
+            if depth.abs() < 1e-3 { continue; }
+
+            for (a, b) in [(ellipse, other), (other, ellipse)] {
+                let push = a.get_penetration(&b);
+                assert_eq!(push.is_some(), depth > 0.0, "{:?} {:?} depth {}", a, b, depth);
+                if let Some(push) = push {
+                    assert!((push.length() - depth).abs() < 2e-3, "{:?} {:?} push {:?} depth {}", a, b, push, depth);
+                    overlapping += 1;
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > 360 && overlapping > 120, "{} {}", checked, overlapping);
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn test_shape_mesh() {
+        use bevy::render::mesh::{Indices, Mesh, VertexAttributeValues};
+        use crate::math::Vec2;
+        use super::{Shape, ShapeMesh};
+
+        let buffers = |mesh: &Mesh| {
+            let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+                Some(VertexAttributeValues::Float32x3(v)) => v.clone(),
+                _ => panic!("Missing positions"),
+            };
+            let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+                Some(VertexAttributeValues::Float32x2(v)) => v.clone(),
+                _ => panic!("Missing uvs"),
+            };
+            let indices = match mesh.indices() {
+                Some(Indices::U32(v)) => v.clone(),
+                _ => panic!("Missing indices"),
+            };
+            (positions, uvs, indices)
+        };
+
+        // Counter-clockwise triangles covering the whole shape
+        let area = |positions: &[[f32; 3]], indices: &[u32]| indices.chunks(3).map(|t| {
+            let (a, b, c) = (positions[t[0] as usize], positions[t[1] as usize], positions[t[2] as usize]);
+            0.5*((b[0] - a[0])*(c[1] - a[1]) - (c[0] - a[0])*(b[1] - a[1]))
+        }).collect::<Vec<f32>>();
+
+        let (positions, uvs, indices) = buffers(&Mesh::from(Shape::rectangle(Vec2::new(5.0, 5.0), Vec2::new(2.0, 1.0))));
+        assert_eq!(positions, vec![[-2.0, -1.0, 0.0], [2.0, -1.0, 0.0], [2.0, 1.0, 0.0], [-2.0, 1.0, 0.0]]);
+        assert_eq!(uvs, vec![[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]]);
+        assert_eq!(area(&positions, &indices), vec![4.0, 4.0]);
+
+        for (incline, invert) in [(false, false), (false, true), (true, false), (true, true)] {
+            let (positions, _, indices) = buffers(&Mesh::from(Shape::triangle(Vec2::ZERO, Vec2::ONE, incline, invert)));
+            assert_eq!(positions.len(), 3);
+            assert_eq!(area(&positions, &indices), vec![2.0]);
+        }
+
+        let (positions, _, indices) = buffers(&ShapeMesh{ shape: Shape::circle(Vec2::ONE, 1.0), segments: 64 }.into());
+        assert_eq!((positions.len(), indices.len()), (65, 64*3));
+        let total: f32 = area(&positions, &indices).iter().sum();
+        assert!(area(&positions, &indices).iter().all(|&a| a > 0.0));
+        assert!((total - std::f32::consts::PI).abs() < 0.01);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        use crate::{math::Vec2, physics::Collider};
+        use super::{Shape, ShapeKind};
+
+        let shapes = [
+            ("Point(origin:(1.0,2.0))",                                      Shape::point(Vec2::new(1.0, 2.0))),
+            ("Circle(origin:(0.0,0.0),radius:1.5)",                          Shape::circle(Vec2::ZERO, 1.5)),
+            ("Ellipse(origin:(0.0,0.0),radius:(1.0,2.0))",                   Shape::ellipse(Vec2::ZERO, Vec2::new(1.0, 2.0))),
+            ("Square(origin:(0.0,0.0),size:0.5)",                            Shape::square(Vec2::ZERO, 0.5)),
+            ("Rectangle(origin:(0.0,0.0),size:(1.0,0.5))",                   Shape::rectangle(Vec2::ZERO, Vec2::new(1.0, 0.5))),
+            ("Triangle(origin:(0.0,0.0),size:(1.0,1.0),incline:true,invert:false)", Shape::triangle(Vec2::ZERO, Vec2::ONE, true, false)),
+        ];
+        for (text, shape) in shapes {
+            assert_eq!(ron::to_string(&shape).unwrap(), text);
+            let parsed: Shape = ron::from_str(text).unwrap();
+            assert_eq!((parsed.origin, parsed.hbound, parsed.shape), (shape.origin, shape.hbound, shape.shape));
+        }
+
+        for kind in [ShapeKind::Ellipse, ShapeKind::Rectangle, ShapeKind::TriangleDeclineNormal, ShapeKind::TriangleDeclineInvert, ShapeKind::TriangleInclineNormal, ShapeKind::TriangleInclineInvert] {
+            assert_eq!(ron::from_str::<ShapeKind>(&ron::to_string(&kind).unwrap()).unwrap(), kind);
+        }
+        assert_eq!(ron::to_string(&ShapeKind::TriangleDeclineInvert).unwrap(), "Triangle(incline:false,invert:true)");
+
+        let projection = Projection::new(-1.0, 2.0);
+        assert_eq!(ron::to_string(&projection).unwrap(), "(near:-1.0,far:2.0)");
+        assert_eq!(ron::from_str::<Projection>("(near:-1.0,far:2.0)").unwrap(), projection);
+        assert!(ron::from_str::<Projection>("(near:2.0,far:-1.0)").is_err());
+
+        let collider: Collider = ron::from_str("(shape: Circle(origin: (1.0, 1.0), radius: 0.5))").unwrap();
+        assert_eq!((collider.shape.origin, collider.shape.hbound), (Vec2::ONE, Vec2::splat(0.5)));
+        let collider: Collider = ron::from_str(&ron::to_string(&collider).unwrap()).unwrap();
+        assert_eq!(collider.shape.shape, ShapeKind::Ellipse);
+    }
+
+    #[test]
+    fn test_shape_transform() {
+        use crate::math::Vec2;
+        use super::{Shape, ShapeKind};
+
+        let same_points = |a: &[Vec2], b: &[Vec2]| a.len() == b.len() && a.iter().all(|p| b.iter().any(|q| (*p - *q).length() < 1e-5));
+        let mirror_x = |p: Vec2| Vec2::new(2.0 - p.x, p.y);
+        let mirror_y = |p: Vec2| Vec2::new(p.x, -4.0 - p.y);
+
+        for (incline, invert) in [(false, false), (false, true), (true, false), (true, true)] {
+            let shape = Shape::triangle(Vec2::new(3.0, 2.0), Vec2::new(1.0, 0.5), incline, invert);
+            let points = shape.get_points_slope();
+
+            let mirrored = shape.mirrored_x(1.0);
+            assert!(same_points(&mirrored.get_points_slope(), &points.map(mirror_x)), "{:?} mirrored_x", shape.shape);
+            let mirrored = shape.mirrored_y(-2.0);
+            assert!(same_points(&mirrored.get_points_slope(), &points.map(mirror_y)), "{:?} mirrored_y", shape.shape);
+
+            let scaled = shape.scaled(Vec2::new(-2.0, 1.0));
+            let expected = points.map(|p| Vec2::new(shape.origin.x - 2.0*(p.x - shape.origin.x), p.y));
+            assert!(same_points(&scaled.get_points_slope(), &expected), "{:?} scaled", shape.shape);
+        }
+
+        let ellipse = Shape::circle(Vec2::ONE, 1.0).scaled(Vec2::new(-2.0, 0.5)).translated(Vec2::X);
+        assert_eq!((ellipse.origin, ellipse.hbound, ellipse.shape), (Vec2::new(2.0, 1.0), Vec2::new(2.0, 0.5), ShapeKind::Ellipse));
+
+        let aabb = Shape::triangle(Vec2::ONE, Vec2::ONE, true, false).aabb();
+        assert_eq!((aabb.origin, aabb.hbound, aabb.shape), (Vec2::ONE, Vec2::ONE, ShapeKind::Rectangle));
+    }
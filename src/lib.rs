@@ -11,7 +11,7 @@ pub mod sync;
 pub mod util;
 pub mod container;
 
-#[cfg(feature = "bevy/render")]
+#[cfg(feature = "render")]
 pub mod camera;
 
 pub mod prelude {
@@ -0,0 +1,412 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use std::{fmt, ops::{Add, Sub, Mul, Div, Rem, Neg, AddAssign, SubAssign, MulAssign, DivAssign}};
+
+use bevy::reflect::Reflect;
+#[cfg(feature = "serde")]
+use bevy::reflect::ReflectDeserialize;
+use num_traits::{Num, Zero, One, Signed, Bounded, ParseFloatError};
+
+use super::{Scalar, Vector};
+
+/// A signed fixed-point number with 32 integer and 32 fractional bits. Every
+/// operation is done with integers, so results are identical on all machines.
+/// Arithmetic saturates rather than overflowing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq, Hash))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Hash, Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fixed(i64);
+
+impl Fixed {
+    pub const FRAC_BITS: u32 = 32;
+
+    pub const ZERO: Self = Self(0);
+    pub const ONE:  Self = Self(1 << Self::FRAC_BITS);
+    pub const MIN:  Self = Self(i64::MIN);
+    pub const MAX:  Self = Self(i64::MAX);
+
+    /// Construct from the raw bits, where one is `1 << FRAC_BITS`
+    pub const fn from_bits(bits: i64) -> Self {
+        Self(bits)
+    }
+
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    pub const fn from_int(value: i32) -> Self {
+        Self((value as i64) << Self::FRAC_BITS)
+    }
+
+    /// Converts from a float, saturating if out of range. Only deterministic
+    /// if the float is, so prefer [Self::from_int] and [Self::from_ratio] in
+    /// simulation code.
+    pub fn from_f64(value: f64) -> Self {
+        Self((value*(1u64 << Self::FRAC_BITS) as f64) as i64)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64/(1u64 << Self::FRAC_BITS) as f64
+    }
+
+    /// Construct from the fraction `numerator/denominator`
+    pub fn from_ratio(numerator: i32, denominator: i32) -> Self {
+        Self::from_int(numerator)/Self::from_int(denominator)
+    }
+
+    /// Rounds towards negative infinity
+    pub fn floor(self) -> Self {
+        Self(self.0 & !((1 << Self::FRAC_BITS) - 1))
+    }
+
+    pub fn fract(self) -> Self {
+        Self(self.0 & ((1 << Self::FRAC_BITS) - 1))
+    }
+
+    fn saturate(value: i128) -> Self {
+        Self(value.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl From<i32> for Fixed {
+    fn from(value: i32) -> Self {
+        Self::from_int(value)
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::saturate((self.0 as i128*rhs.0 as i128) >> Self::FRAC_BITS)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    /// Divides, saturating when dividing by zero
+    fn div(self, rhs: Self) -> Self {
+        match rhs.0 {
+            0 if self.0 < 0 => Self::MIN,
+            0 if self.0 > 0 => Self::MAX,
+            0               => Self::ZERO,
+            _               => Self::saturate(((self.0 as i128) << Self::FRAC_BITS)/rhs.0 as i128),
+        }
+    }
+}
+
+impl Rem for Fixed {
+    type Output = Self;
+
+    /// The remainder of division, zero when dividing by zero
+    fn rem(self, rhs: Self) -> Self {
+        // Only fails for zero, or `MIN % -1` which overflows despite a remainder of zero
+        Self(self.0.checked_rem(rhs.0).unwrap_or(0))
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg())
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self*rhs;
+    }
+}
+
+impl DivAssign for Fixed {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self/rhs;
+    }
+}
+
+impl Zero for Fixed {
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl One for Fixed {
+    fn one() -> Self {
+        Self::ONE
+    }
+}
+
+impl Num for Fixed {
+    type FromStrRadixErr = ParseFloatError;
+
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        f64::from_str_radix(str, radix).map(Self::from_f64)
+    }
+}
+
+impl Signed for Fixed {
+    fn abs(&self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    fn abs_sub(&self, other: &Self) -> Self {
+        if *self <= *other { Self::ZERO } else { *self - *other }
+    }
+
+    fn signum(&self) -> Self {
+        Self::from_int(self.0.signum() as i32)
+    }
+
+    fn is_positive(&self) -> bool {
+        self.0 > 0
+    }
+
+    fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+}
+
+impl Bounded for Fixed {
+    fn min_value() -> Self {
+        Self::MIN
+    }
+
+    fn max_value() -> Self {
+        Self::MAX
+    }
+}
+
+impl Scalar for Fixed {
+    type Vec2 = FixedVec2;
+
+    const FRAC_1_SQRT_2: Self = Self(3037000500);
+    const TAU: Self = Fixed::TAU;
+
+    fn from_f32(value: f32) -> Self {
+        Self::from_f64(value as f64)
+    }
+
+    fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    /// Calculates the square root bit by bit, zero for negative values
+    fn sqrt(self) -> Self {
+        if self.0 <= 0 { return Self::ZERO; }
+
+        let value = (self.0 as u128) << Self::FRAC_BITS;
+        let mut result = 0u128;
+        let mut bit = 1u128 << ((127 - value.leading_zeros()) & !1);
+        let mut rest = value;
+        while bit != 0 {
+            if rest >= result + bit {
+                rest -= result + bit;
+                result = (result >> 1) + bit;
+            } else {
+                result >>= 1;
+            }
+            bit >>= 2;
+        }
+        Self(result as i64)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        Fixed::sin_cos(self)
+    }
+
+    fn infinity() -> Self {
+        Self::MAX
+    }
+
+    fn neg_infinity() -> Self {
+        // Not MIN, so negating gives infinity
+        -Self::MAX
+    }
+
+    fn half(self) -> Self {
+        Self(self.0 >> 1)
+    }
+}
+
+/// A 2D vector of [Fixed] numbers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(not(feature = "serde"), reflect_value(PartialEq, Hash))]
+#[cfg_attr(feature = "serde", reflect_value(PartialEq, Hash, Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FixedVec2 {
+    pub x: Fixed,
+    pub y: Fixed,
+}
+
+impl FixedVec2 {
+    pub const ZERO: Self = Self::new(Fixed::ZERO, Fixed::ZERO);
+    pub const ONE:  Self = Self::new(Fixed::ONE,  Fixed::ONE );
+    pub const X:    Self = Self::new(Fixed::ONE,  Fixed::ZERO);
+    pub const Y:    Self = Self::new(Fixed::ZERO, Fixed::ONE );
+
+    pub const fn new(x: Fixed, y: Fixed) -> Self {
+        Self{ x, y }
+    }
+
+    pub fn from_f32(x: f32, y: f32) -> Self {
+        Self::new(Fixed::from_f32(x), Fixed::from_f32(y))
+    }
+}
+
+/// Matches [super::ExtVec2], using lookup tables for trigonometry, see
+/// [Fixed::sin_cos] and [Fixed::atan2]
+impl FixedVec2 {
+    pub fn angle(self) -> Fixed {
+        self.y.atan2(self.x)
+    }
+
+    pub fn from_angle(angle: Fixed) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::new(c, s)
+    }
+
+    pub fn rotated_by(self, angle: Fixed) -> Self {
+        self.rotated_by_vec(Self::from_angle(angle))
+    }
+
+    pub fn rotated_by_vec(self, rot_vec: FixedVec2) -> Self {
+        Self::new(
+            self.x*rot_vec.x - self.y*rot_vec.y,
+            self.x*rot_vec.y + self.y*rot_vec.x,
+        )
+    }
+
+    pub fn negate_x(self) -> Self {
+        Self::new(-self.x,  self.y)
+    }
+
+    pub fn negate_y(self) -> Self {
+        Self::new( self.x, -self.y)
+    }
+
+    pub fn into_tuple(self) -> (Fixed, Fixed) {
+        (self.x, self.y)
+    }
+}
+
+impl Add for FixedVec2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for FixedVec2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul for FixedVec2 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.x*rhs.x, self.y*rhs.y)
+    }
+}
+
+impl Mul<Fixed> for FixedVec2 {
+    type Output = Self;
+
+    fn mul(self, rhs: Fixed) -> Self {
+        Self::new(self.x*rhs, self.y*rhs)
+    }
+}
+
+impl Div<Fixed> for FixedVec2 {
+    type Output = Self;
+
+    fn div(self, rhs: Fixed) -> Self {
+        Self::new(self.x/rhs, self.y/rhs)
+    }
+}
+
+impl Neg for FixedVec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for FixedVec2 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for FixedVec2 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Vector<Fixed> for FixedVec2 {
+    const ZERO: Self = FixedVec2::ZERO;
+    const ONE:  Self = FixedVec2::ONE;
+    const X:    Self = FixedVec2::X;
+    const Y:    Self = FixedVec2::Y;
+
+    fn new(x: Fixed, y: Fixed) -> Self {
+        FixedVec2::new(x, y)
+    }
+
+    fn x(self) -> Fixed {
+        self.x
+    }
+
+    fn y(self) -> Fixed {
+        self.y
+    }
+}
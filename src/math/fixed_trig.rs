@@ -0,0 +1,156 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use num_traits::Signed;
+
+use super::Fixed;
+
+/// Log2 of the number of table entries per quarter turn
+const TABLE_BITS: u32 = 10;
+const QUARTER: usize = 1 << TABLE_BITS;
+
+/// Fractional bits used while building the tables
+const BUILD_BITS: u32 = 60;
+const BUILD_PI: i128 = 3622009729038561421;
+
+/// Table entries per radian, with 32 fractional bits
+const ENTRIES_PER_RADIAN: i128 = 2799883368761;
+
+/// Sine of each step of a quarter turn, both ends included
+const SIN_TABLE: [i64; QUARTER + 1] = build_sin_table();
+/// Arctangent of each step between zero and one, both ends included
+const ATAN_TABLE: [i64; QUARTER + 1] = build_atan_table();
+
+// The tables are built with integer maths at compile time, so they're
+// identical everywhere and don't depend on the platform's float functions.
+
+const fn build_mul(a: i128, b: i128) -> i128 {
+    (a*b) >> BUILD_BITS
+}
+
+const fn build_round(value: i128) -> i64 {
+    let half = 1 << (BUILD_BITS - Fixed::FRAC_BITS - 1);
+    ((value + half) >> (BUILD_BITS - Fixed::FRAC_BITS)) as i64
+}
+
+/// Taylor series of sine, accurate for small angles
+const fn build_sin(x: i128) -> i128 {
+    let x2 = build_mul(x, x);
+    let (mut sum, mut term, mut n) = (x, x, 1);
+    while term != 0 {
+        term = -build_mul(term, x2)/((2*n)*(2*n + 1));
+        sum += term;
+        n += 1;
+    }
+    sum
+}
+
+/// Taylor series of arctangent, accurate for `x` within a half of zero
+const fn build_atan(x: i128) -> i128 {
+    let x2 = build_mul(x, x);
+    let (mut sum, mut power, mut n) = (x, x, 1);
+    while power != 0 {
+        power = -build_mul(power, x2);
+        sum += power/(2*n + 1);
+        n += 1;
+    }
+    sum
+}
+
+const fn build_sin_table() -> [i64; QUARTER + 1] {
+    let mut table = [0; QUARTER + 1];
+    let mut index = 0;
+    while index <= QUARTER {
+        table[index] = build_round(build_sin(BUILD_PI/2*index as i128/QUARTER as i128));
+        index += 1;
+    }
+    table
+}
+
+const fn build_atan_table() -> [i64; QUARTER + 1] {
+    let one = 1 << BUILD_BITS;
+    let mut table = [0; QUARTER + 1];
+    let mut index = 0;
+    while index <= QUARTER {
+        let x = one*index as i128/QUARTER as i128;
+        // Above a half, atan(x) = π/4 + atan((x - 1)/(x + 1)) converges faster
+        table[index] = build_round(if 2*x <= one {
+            build_atan(x)
+        } else {
+            BUILD_PI/4 + build_atan(((x - one) << BUILD_BITS)/(x + one))
+        });
+        index += 1;
+    }
+    table
+}
+
+/// Linearly interpolates between table entries, by a fraction with 32 bits
+fn lerp(a: i64, b: i64, fraction: i64) -> i64 {
+    a + (((b - a) as i128*fraction as i128) >> Fixed::FRAC_BITS) as i64
+}
+
+/// Sine of a position in quarter turn table entries, with 32 fractional bits
+fn sin_entries(position: i128) -> Fixed {
+    let mask = (1i128 << Fixed::FRAC_BITS) - 1;
+    let fraction = (position & mask) as i64;
+    let entry = ((position >> Fixed::FRAC_BITS) as usize) & (4*QUARTER - 1);
+    let (quadrant, index) = (entry >> TABLE_BITS, entry & (QUARTER - 1));
+    let value = match quadrant {
+        0 | 2 => lerp(SIN_TABLE[index],           SIN_TABLE[index + 1],           fraction),
+        _     => lerp(SIN_TABLE[QUARTER - index], SIN_TABLE[QUARTER - index - 1], fraction),
+    };
+    Fixed::from_bits(if quadrant < 2 { value } else { -value })
+}
+
+impl Fixed {
+    pub const PI:        Self = Self::from_bits(13493037705);
+    pub const TAU:       Self = Self::from_bits(26986075409);
+    pub const FRAC_PI_2: Self = Self::from_bits(6746518852);
+    pub const FRAC_PI_4: Self = Self::from_bits(3373259426);
+
+    /// Sine of an angle in radians, using a lookup table
+    pub fn sin(self) -> Self {
+        sin_entries(self.to_entries())
+    }
+
+    /// Cosine of an angle in radians, using a lookup table
+    pub fn cos(self) -> Self {
+        sin_entries(self.to_entries() + ((QUARTER as i128) << Self::FRAC_BITS))
+    }
+
+    pub fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+
+    /// Converts an angle to a position in table entries, with 32 fractional bits
+    fn to_entries(self) -> i128 {
+        (self.to_bits() as i128*ENTRIES_PER_RADIAN) >> Self::FRAC_BITS
+    }
+
+    /// Arctangent in radians, using a lookup table
+    pub fn atan(self) -> Self {
+        self.atan2(Self::ONE)
+    }
+
+    /// Four quadrant arctangent of `self` and `x` in radians, between -π and π.
+    /// Zero if both are zero.
+    pub fn atan2(self, x: Self) -> Self {
+        let (ay, ax) = (self.abs(), x.abs());
+        if ay == Self::ZERO && ax == Self::ZERO { return Self::ZERO; }
+
+        // Reduced to the first octant, so the table only covers zero to one
+        let angle = if ay <= ax { atan_unit(ay/ax) } else { Self::FRAC_PI_2 - atan_unit(ax/ay) };
+        let angle = if x < Self::ZERO { Self::PI - angle } else { angle };
+        if self < Self::ZERO { -angle } else { angle }
+    }
+}
+
+/// Arctangent of a value between zero and one
+fn atan_unit(x: Fixed) -> Fixed {
+    let position = x.to_bits() as i128*QUARTER as i128;
+    let index = (position >> Fixed::FRAC_BITS) as usize;
+    if index >= QUARTER { return Fixed::from_bits(ATAN_TABLE[QUARTER]); }
+    let fraction = (position & ((1 << Fixed::FRAC_BITS) - 1)) as i64;
+    Fixed::from_bits(lerp(ATAN_TABLE[index], ATAN_TABLE[index + 1], fraction))
+}
@@ -2,9 +2,20 @@
 ** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
 \*========================================================================*/
 
+#[cfg(test)]
+mod test;
+
 mod ext_vec2;
 pub use ext_vec2::*;
 
+mod scalar;
+pub use scalar::*;
+
+mod fixed;
+pub use fixed::*;
+
+mod fixed_trig;
+
 pub mod prelude {
     // We re-export bevy math so we can port to 
     // a different math library more easily
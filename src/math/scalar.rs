@@ -0,0 +1,173 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use std::{fmt::Debug, ops::{Add, Sub, Mul, Div, Neg, AddAssign, SubAssign}};
+
+use bevy::{math::DVec2, reflect::Reflect};
+use num_traits::{Num, Signed, Bounded};
+
+use super::prelude::Vec2;
+
+/// A number type that collision can be calculated with, allowing a choice
+/// between precision, speed and determinism.
+pub trait Scalar: Num + Signed + Bounded + Copy + PartialOrd + Debug + Default + Reflect {
+    /// The 2D vector made of this scalar
+    type Vec2: Vector<Self>;
+
+    const FRAC_1_SQRT_2: Self;
+    const TAU: Self;
+
+    fn from_f32(value: f32) -> Self;
+    fn to_f32(self) -> f32;
+
+    fn sqrt(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+
+    /// Larger than any finite value, or the largest value if there's no infinity
+    fn infinity() -> Self;
+    /// Smaller than any finite value, or the smallest value if there's no infinity
+    fn neg_infinity() -> Self;
+
+    fn half(self) -> Self {
+        self/(Self::one() + Self::one())
+    }
+
+    fn min(self, other: Self) -> Self {
+        if other < self { other } else { self }
+    }
+
+    fn max(self, other: Self) -> Self {
+        if other > self { other } else { self }
+    }
+}
+
+/// A 2D vector of a [Scalar], with the operations needed for collision
+pub trait Vector<S: Scalar>: 
+    Copy + PartialEq + Debug + Default + Reflect +
+    Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self> +
+    Mul<S, Output = Self> + Div<S, Output = Self> + AddAssign + SubAssign
+{
+    const ZERO: Self;
+    const ONE:  Self;
+    const X:    Self;
+    const Y:    Self;
+
+    fn new(x: S, y: S) -> Self;
+    fn x(self) -> S;
+    fn y(self) -> S;
+
+    fn dot(self, other: Self) -> S {
+        self.x()*other.x() + self.y()*other.y()
+    }
+
+    fn length_squared(self) -> S {
+        self.dot(self)
+    }
+
+    fn abs(self) -> Self {
+        Self::new(self.x().abs(), self.y().abs())
+    }
+
+    /// Normalizes the vector, returning `None` if it has no length
+    fn try_normalize(self) -> Option<Self> {
+        let length = self.length_squared().sqrt();
+        if length > S::zero() { Some(self/length) } else { None }
+    }
+}
+
+impl Scalar for f32 {
+    type Vec2 = Vec2;
+
+    const FRAC_1_SQRT_2: Self = std::f32::consts::FRAC_1_SQRT_2;
+    const TAU: Self = std::f32::consts::TAU;
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        f32::sin_cos(self)
+    }
+
+    fn infinity() -> Self {
+        f32::INFINITY
+    }
+
+    fn neg_infinity() -> Self {
+        f32::NEG_INFINITY
+    }
+}
+
+impl Scalar for f64 {
+    type Vec2 = DVec2;
+
+    const FRAC_1_SQRT_2: Self = std::f64::consts::FRAC_1_SQRT_2;
+    const TAU: Self = std::f64::consts::TAU;
+
+    fn from_f32(value: f32) -> Self {
+        value as f64
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        f64::sin_cos(self)
+    }
+
+    fn infinity() -> Self {
+        f64::INFINITY
+    }
+
+    fn neg_infinity() -> Self {
+        f64::NEG_INFINITY
+    }
+}
+
+macro_rules! impl_vector_glam {
+    ($vec:ty, $scalar:ty) => {
+        impl Vector<$scalar> for $vec {
+            const ZERO: Self = <$vec>::ZERO;
+            const ONE:  Self = <$vec>::ONE;
+            const X:    Self = <$vec>::X;
+            const Y:    Self = <$vec>::Y;
+
+            fn new(x: $scalar, y: $scalar) -> Self {
+                <$vec>::new(x, y)
+            }
+
+            fn x(self) -> $scalar {
+                self.x
+            }
+
+            fn y(self) -> $scalar {
+                self.y
+            }
+
+            fn dot(self, other: Self) -> $scalar {
+                <$vec>::dot(self, other)
+            }
+
+            fn try_normalize(self) -> Option<Self> {
+                <$vec>::try_normalize(self)
+            }
+        }
+    };
+}
+
+impl_vector_glam!(Vec2,  f32);
+impl_vector_glam!(DVec2, f64);
@@ -0,0 +1,89 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use super::*;
+
+#[test]
+fn fixed_arithmetic() {
+    let (a, b) = (Fixed::from_ratio(3, 2), Fixed::from_int(-4));
+    assert_eq!(a + b, Fixed::from_ratio(-5, 2));
+    assert_eq!(a - b, Fixed::from_ratio(11, 2));
+    assert_eq!(a*b,   Fixed::from_int(-6));
+    assert_eq!(b/a,   Fixed::from_ratio(-8, 3));
+    assert_eq!(a.floor(), Fixed::ONE);
+    assert_eq!(b.floor(), b);
+    assert_eq!(Fixed::from_f32(-1.25).floor(), Fixed::from_int(-2));
+    assert_eq!(a.to_f32(), 1.5);
+}
+
+#[test]
+fn fixed_saturates() {
+    assert_eq!(Fixed::MAX + Fixed::ONE, Fixed::MAX);
+    assert_eq!(Fixed::from_int(1 << 20)*Fixed::from_int(1 << 20), Fixed::MAX);
+    assert_eq!(Fixed::ONE/Fixed::ZERO, Fixed::MAX);
+    assert_eq!(-Fixed::ONE/Fixed::ZERO, Fixed::MIN);
+    assert_eq!(Fixed::ONE%Fixed::ZERO, Fixed::ZERO);
+    assert_eq!(Fixed::MIN%Fixed::from_bits(-1), Fixed::ZERO);
+    assert_eq!(Fixed::from_ratio(7, 2)%Fixed::ONE, Fixed::from_ratio(1, 2));
+    assert_eq!(-Fixed::MIN, Fixed::MAX);
+    assert_eq!(-Fixed::neg_infinity(), Fixed::infinity());
+}
+
+#[test]
+fn fixed_sqrt() {
+    assert_eq!(Fixed::from_int(16).sqrt(), Fixed::from_int(4));
+    assert_eq!(Fixed::from_ratio(1, 4).sqrt(), Fixed::from_ratio(1, 2));
+    assert_eq!(Fixed::from_int(-1).sqrt(), Fixed::ZERO);
+    assert!((Fixed::from_int(2).sqrt().to_f64() - std::f64::consts::SQRT_2).abs() < 1e-9);
+    assert!((Fixed::FRAC_1_SQRT_2.to_f64() - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-9);
+}
+
+#[test]
+fn fixed_vector() {
+    let v = FixedVec2::new(Fixed::from_int(3), Fixed::from_int(4));
+    assert_eq!(v.length_squared(), Fixed::from_int(25));
+    assert_eq!(v.try_normalize(), Some(FixedVec2::new(Fixed::from_ratio(3, 5), Fixed::from_ratio(4, 5))));
+    assert_eq!(FixedVec2::ZERO.try_normalize(), None);
+    assert_eq!(-v + v*Fixed::from_int(2), v);
+}
+
+#[test]
+fn fixed_trig() {
+    // The constants are rounded, so may be off by the smallest step
+    let near = |a: Fixed, b: Fixed| (a.to_bits() - b.to_bits()).abs() <= 1;
+    assert_eq!(Fixed::ZERO.sin_cos(), (Fixed::ZERO, Fixed::ONE));
+    assert!(near(Fixed::FRAC_PI_2.sin(), Fixed::ONE));
+    assert!(near(Fixed::PI.cos(), -Fixed::ONE));
+    assert!(near(Fixed::ONE.atan(), Fixed::FRAC_PI_4));
+
+    for step in -200..=200 {
+        let angle = step as f64*0.0625;
+        let (sin, cos) = Fixed::from_f64(angle).sin_cos();
+        assert!((sin.to_f64() - angle.sin()).abs() < 1e-6, "sin {}", angle);
+        assert!((cos.to_f64() - angle.cos()).abs() < 1e-6, "cos {}", angle);
+
+        let (y, x) = (angle.sin()*3.0, (angle*1.7).cos()*2.0);
+        let atan2 = Fixed::from_f64(y).atan2(Fixed::from_f64(x));
+        assert!((atan2.to_f64() - y.atan2(x)).abs() < 1e-6, "atan2 {} {}", y, x);
+    }
+    assert_eq!(Fixed::ZERO.atan2(Fixed::ZERO), Fixed::ZERO);
+}
+
+#[test]
+fn fixed_vector_angles() {
+    let v = FixedVec2::new(Fixed::from_int(3), Fixed::from_int(4));
+    assert_eq!(v.negate_x().into_tuple(), (Fixed::from_int(-3), Fixed::from_int(4)));
+    assert_eq!(v.negate_y().into_tuple(), (Fixed::from_int(3), Fixed::from_int(-4)));
+    assert_eq!(FixedVec2::Y.angle(), Fixed::FRAC_PI_2);
+    assert_eq!(FixedVec2::from_angle(Fixed::ZERO), FixedVec2::X);
+    assert_eq!(FixedVec2::X.rotated_by_vec(FixedVec2::Y), FixedVec2::Y);
+
+    let rotated = v.rotated_by(Fixed::FRAC_PI_2);
+    assert!((rotated.x.to_f64() + 4.0).abs() < 1e-6 && (rotated.y.to_f64() - 3.0).abs() < 1e-6, "{:?}", rotated);
+    assert!((rotated.angle() - v.angle() - Fixed::FRAC_PI_2).to_f64().abs() < 1e-6);
+
+    // Must match on every machine, so any change to the tables is caught
+    let bits = FixedVec2::from_angle(Fixed::from_ratio(1, 3)).into_tuple();
+    assert_eq!((bits.0.to_bits(), bits.1.to_bits()), (4058558178, 1405290174));
+}
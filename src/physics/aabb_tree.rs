@@ -0,0 +1,233 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::{
+    ecs::entity::Entity,
+    utils::{HashSet, StableHashMap},
+};
+
+use crate::collision::{Shape, Projection};
+
+use super::Broadphase;
+
+const NULL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct AabbTreeNode {
+    x: Projection,
+    y: Projection,
+    parent: usize,
+    left:   usize,
+    right:  usize,
+    entity: Option<Entity>,
+}
+
+impl AabbTreeNode {
+    fn is_leaf(&self) -> bool {
+        self.entity.is_some()
+    }
+}
+
+/// A dynamic bounding volume hierarchy of axis-aligned boxes. Leaves are
+/// fattened by a margin so small movements don't require reinsertion. Suits
+/// sparse worlds with large empty regions and widely varying object sizes.
+#[derive(Clone)]
+pub struct AabbTree {
+    margin:   f32,
+    root:     usize,
+    nodes:    Vec<AabbTreeNode>,
+    freelist: Vec<usize>,
+    leaves:   StableHashMap<Entity, (usize, Projection, Projection)>,
+}
+
+impl AabbTree {
+    /// Creates a new tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `margin` The distance to fatten leaf bounds by on each side
+    pub fn new(margin: f32) -> Self {
+        Self{
+            margin,
+            root:     NULL,
+            nodes:    Default::default(),
+            freelist: Default::default(),
+            leaves:   Default::default(),
+        }
+    }
+
+    fn allocate(&mut self, node: AabbTreeNode) -> usize {
+        if let Some(index) = self.freelist.pop() {
+            self.nodes[index] = node;
+            index
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL {
+            self.root = leaf;
+            self.nodes[leaf].parent = NULL;
+            return;
+        }
+
+        // Descend towards the child whose bounds grow the least
+        let AabbTreeNode{x, y, ..} = self.nodes[leaf];
+        let mut index = self.root;
+        while !self.nodes[index].is_leaf() {
+            let AabbTreeNode{left, right, ..} = self.nodes[index];
+            index = if self.growth(left, x, y) <= self.growth(right, x, y) { left } else { right };
+        }
+
+        let sibling = index;
+        let parent_old = self.nodes[sibling].parent;
+        let parent_new = self.allocate(AabbTreeNode{
+            x: Projection::covering_both(self.nodes[sibling].x, x),
+            y: Projection::covering_both(self.nodes[sibling].y, y),
+            parent: parent_old,
+            left:   sibling,
+            right:  leaf,
+            entity: None,
+        });
+        self.nodes[sibling].parent = parent_new;
+        self.nodes[leaf   ].parent = parent_new;
+
+        if parent_old == NULL {
+            self.root = parent_new;
+        } else {
+            self.replace_child(parent_old, sibling, parent_new);
+            self.refit(parent_old);
+        }
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL;
+            return;
+        }
+
+        let parent  = self.nodes[leaf].parent;
+        let grand   = self.nodes[parent].parent;
+        let sibling = if self.nodes[parent].left == leaf { self.nodes[parent].right } else { self.nodes[parent].left };
+
+        self.nodes[sibling].parent = grand;
+        if grand == NULL {
+            self.root = sibling;
+        } else {
+            self.replace_child(grand, parent, sibling);
+            self.refit(grand);
+        }
+        self.freelist.push(parent);
+    }
+
+    fn replace_child(&mut self, parent: usize, from: usize, to: usize) {
+        let node = &mut self.nodes[parent];
+        if node.left == from { node.left = to; } else { node.right = to; }
+    }
+
+    fn refit(&mut self, mut index: usize) {
+        while index != NULL {
+            let AabbTreeNode{left, right, parent, ..} = self.nodes[index];
+            self.nodes[index].x = Projection::covering_both(self.nodes[left].x, self.nodes[right].x);
+            self.nodes[index].y = Projection::covering_both(self.nodes[left].y, self.nodes[right].y);
+            index = parent;
+        }
+    }
+
+    fn growth(&self, index: usize, x: Projection, y: Projection) -> f32 {
+        let node = &self.nodes[index];
+        let x_new = Projection::covering_both(node.x, x);
+        let y_new = Projection::covering_both(node.y, y);
+        (x_new.length() + y_new.length()) - (node.x.length() + node.y.length())
+    }
+
+    fn query_into<F: FnMut(Entity)>(&self, x: Projection, y: Projection, mut func: F) {
+        if self.root == NULL { return; }
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if x.are_separate(node.x) || y.are_separate(node.y) { continue; }
+            match node.entity {
+                Some(entity) => {
+                    let (_, leaf_x, leaf_y) = self.leaves[&entity];
+                    if !x.are_separate(leaf_x) && !y.are_separate(leaf_y) { func(entity); }
+                },
+                None => {
+                    stack.push(node.left);
+                    stack.push(node.right);
+                },
+            }
+        }
+    }
+}
+
+impl Broadphase for AabbTree {
+    type Prepared = (Projection, Projection);
+    type Snapshot = Self;
+
+    fn prepare(&self, shape: Shape) -> Self::Prepared {
+        shape.project_aligned()
+    }
+
+    fn update_prepared(&mut self, entity: Entity, (x, y): Self::Prepared) {
+        if let Some(&(leaf, _, _)) = self.leaves.get(&entity) {
+            self.leaves.insert(entity, (leaf, x, y));
+
+            // Still within the fattened bounds, tree doesn't need to change
+            let node = &self.nodes[leaf];
+            if Projection::covering_both(node.x, x) == node.x && Projection::covering_both(node.y, y) == node.y { return; }
+
+            self.remove_leaf(leaf);
+            self.nodes[leaf].x = Projection::symmetrical(x.mid(), x.length()/2.0 + self.margin);
+            self.nodes[leaf].y = Projection::symmetrical(y.mid(), y.length()/2.0 + self.margin);
+            self.insert_leaf(leaf);
+        } else {
+            let leaf = self.allocate(AabbTreeNode{
+                x: Projection::symmetrical(x.mid(), x.length()/2.0 + self.margin),
+                y: Projection::symmetrical(y.mid(), y.length()/2.0 + self.margin),
+                parent: NULL,
+                left:   NULL,
+                right:  NULL,
+                entity: Some(entity),
+            });
+            self.leaves.insert(entity, (leaf, x, y));
+            self.insert_leaf(leaf);
+        }
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        if let Some((leaf, _, _)) = self.leaves.remove(&entity) {
+            self.remove_leaf(leaf);
+            self.freelist.push(leaf);
+        }
+    }
+
+    fn query(&self, x: Projection, y: Projection) -> HashSet<Entity> {
+        let mut result: HashSet<Entity> = Default::default();
+        self.query_into(x, y, |entity| { result.insert(entity); });
+        result
+    }
+
+    fn query_pairs(&self) -> HashSet<(Entity, Entity)> {
+        let mut result: HashSet<(Entity, Entity)> = Default::default();
+        for (&a, &(_, x, y)) in &self.leaves {
+            self.query_into(x, y, |b| if a < b { result.insert((a, b)); });
+        }
+        result
+    }
+
+    fn for_each_bounds<F: FnMut(Entity, Projection, Projection)>(&self, mut func: F) {
+        self.leaves.iter().for_each(|(&entity, &(_, x, y))| func(entity, x, y));
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.clone()
+    }
+
+    fn restore(&mut self, snapshot: &Self::Snapshot) {
+        self.clone_from(snapshot);
+    }
+}
@@ -0,0 +1,172 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::{
+    ecs::entity::Entity,
+    utils::HashSet,
+};
+
+use crate::collision::{Shape, Projection};
+
+use super::{CacheGrid, CacheGridData, CacheGridSnapshot, CacheGridHierarchy, CacheGridHierarchySnapshot};
+
+/// A spatial structure that tracks the axis-aligned bounds of entities and
+/// quickly finds candidates for narrow-phase collision tests. Results may
+/// contain false positives, but never false negatives.
+pub trait Broadphase: Send + Sync {
+    /// Data calculated from a shape ahead of insertion, see [Self::prepare]
+    type Prepared: Copy + Send + Sync;
+
+    /// A saved copy of the contents of the broadphase, see [Self::snapshot]
+    type Snapshot: Send + Sync;
+
+    /// Calculates the data required to insert a shape. This only requires shared
+    /// access, so can be performed in parallel for many shapes.
+    fn prepare(&self, shape: Shape) -> Self::Prepared;
+
+    /// Inserts the entity, or updates its bounds if it's already present
+    fn update_prepared(&mut self, entity: Entity, prepared: Self::Prepared);
+
+    /// Inserts or updates many entities at once, implementations may batch this
+    fn update_many<I: IntoIterator<Item = (Entity, Self::Prepared)>>(&mut self, items: I) {
+        for (entity, prepared) in items {
+            self.update_prepared(entity, prepared);
+        }
+    }
+
+    /// Inserts the entity, or updates its bounds if it's already present
+    fn update(&mut self, entity: Entity, shape: Shape) {
+        let prepared = self.prepare(shape);
+        self.update_prepared(entity, prepared);
+    }
+
+    /// Removes the entity, does nothing if it isn't present
+    fn remove(&mut self, entity: Entity);
+
+    /// Finds all entities which may overlap the given axis-aligned bounds
+    fn query(&self, x: Projection, y: Projection) -> HashSet<Entity>;
+
+    /// Finds all pairs of entities which may overlap. The first entity of
+    /// each pair is always the lesser.
+    fn query_pairs(&self) -> HashSet<(Entity, Entity)>;
+
+    /// Like [Self::query], but sorted by entity so iteration is deterministic
+    fn query_sorted(&self, x: Projection, y: Projection) -> Vec<Entity> {
+        into_sorted(self.query(x, y))
+    }
+
+    /// Like [Self::query_pairs], but sorted by entities so iteration is deterministic
+    fn query_pairs_sorted(&self) -> Vec<(Entity, Entity)> {
+        into_sorted(self.query_pairs())
+    }
+
+    /// Visits each entity along with its stored bounds, which may be larger
+    /// than the bounds it was inserted with.
+    fn for_each_bounds<F: FnMut(Entity, Projection, Projection)>(&self, func: F);
+
+    /// Saves the contents of the broadphase, ie. for rollback
+    fn snapshot(&self) -> Self::Snapshot;
+
+    /// Restores the contents from a snapshot taken from this broadphase
+    fn restore(&mut self, snapshot: &Self::Snapshot);
+}
+
+impl Broadphase for CacheGrid {
+    type Prepared = CacheGridData;
+    type Snapshot = CacheGridSnapshot;
+
+    fn prepare(&self, shape: Shape) -> Self::Prepared {
+        CacheGrid::prepare(self, shape)
+    }
+
+    fn update_prepared(&mut self, entity: Entity, prepared: Self::Prepared) {
+        CacheGrid::update_prepared(self, entity, prepared)
+    }
+
+    fn update_many<I: IntoIterator<Item = (Entity, Self::Prepared)>>(&mut self, items: I) {
+        CacheGrid::insert_many(self, items)
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        CacheGrid::remove(self, entity)
+    }
+
+    fn query(&self, x: Projection, y: Projection) -> HashSet<Entity> {
+        CacheGrid::query(self, x, y)
+    }
+
+    fn query_sorted(&self, x: Projection, y: Projection) -> Vec<Entity> {
+        CacheGrid::query_sorted(self, x, y)
+    }
+
+    fn query_pairs(&self) -> HashSet<(Entity, Entity)> {
+        let mut result: HashSet<(Entity, Entity)> = Default::default();
+        self.query_pairs_into(&mut result);
+        result
+    }
+
+    fn for_each_bounds<F: FnMut(Entity, Projection, Projection)>(&self, mut func: F) {
+        self.iter_bounds().for_each(|(entity, x, y)| func(entity, x, y));
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        CacheGrid::snapshot(self)
+    }
+
+    fn restore(&mut self, snapshot: &Self::Snapshot) {
+        CacheGrid::restore(self, snapshot)
+    }
+}
+
+impl Broadphase for CacheGridHierarchy {
+    type Prepared = (usize, CacheGridData);
+    type Snapshot = CacheGridHierarchySnapshot;
+
+    fn prepare(&self, shape: Shape) -> Self::Prepared {
+        CacheGridHierarchy::prepare(self, shape)
+    }
+
+    fn update_prepared(&mut self, entity: Entity, prepared: Self::Prepared) {
+        CacheGridHierarchy::update_prepared(self, entity, prepared)
+    }
+
+    fn update_many<I: IntoIterator<Item = (Entity, Self::Prepared)>>(&mut self, items: I) {
+        CacheGridHierarchy::insert_many(self, items)
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        CacheGridHierarchy::remove(self, entity)
+    }
+
+    fn query(&self, x: Projection, y: Projection) -> HashSet<Entity> {
+        CacheGridHierarchy::query(self, x, y)
+    }
+
+    fn query_sorted(&self, x: Projection, y: Projection) -> Vec<Entity> {
+        CacheGridHierarchy::query_sorted(self, x, y)
+    }
+
+    fn query_pairs(&self) -> HashSet<(Entity, Entity)> {
+        CacheGridHierarchy::query_pairs(self)
+    }
+
+    fn for_each_bounds<F: FnMut(Entity, Projection, Projection)>(&self, mut func: F) {
+        self.iter_bounds().for_each(|(entity, x, y)| func(entity, x, y));
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        CacheGridHierarchy::snapshot(self)
+    }
+
+    fn restore(&mut self, snapshot: &Self::Snapshot) {
+        CacheGridHierarchy::restore(self, snapshot)
+    }
+}
+
+/// Collects a set into a sorted vector, for deterministic iteration
+pub(crate) fn into_sorted<T: Ord>(set: HashSet<T>) -> Vec<T> {
+    let mut result: Vec<T> = set.into_iter().collect();
+    result.sort_unstable();
+    result
+}
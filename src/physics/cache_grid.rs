@@ -2,37 +2,44 @@
 ** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
 \*========================================================================*/
 
-use std::collections::VecDeque;
+use std::{collections::VecDeque, hash::Hash, sync::{Arc, atomic::{AtomicUsize, Ordering}}};
 
 use bevy::{
     ecs::entity::Entity,
-    utils::{HashMap, HashSet, hashbrown::hash_map::Entry}, 
+    utils::{HashSet, StableHashMap, StableHashSet, hashbrown::hash_map::Entry}, 
 };
 
 use crate::collision::{Shape, Projection};
 
+use super::{CacheGridStats, CACHE_GRID_HISTOGRAM_LEN};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct CacheGridKey(i32, i32);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct CacheGridAxis {
-    near: i32,
-    far:  i32    
+pub(super) struct CacheGridAxis {
+    pub(super) near: i32,
+    pub(super) far:  i32    
 }
 
 impl CacheGridAxis {
-    fn from(projection: Projection, scale: f32) -> Self {
+    pub(super) fn from(projection: Projection, scale: f32) -> Self {
         Self{
             near: (projection.near()*scale).floor() as i32,
             far:  (projection.far() *scale).ceil()  as i32
         }
     }
+
+    fn into_projection(self, scale: f32) -> Projection {
+        Projection::new_unchecked(self.near as f32/scale, self.far as f32/scale)
+    }
 }
 
+/// The range of cells occupied by a key, see [CacheGrid::prepare]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct CacheGridData {
-    x: CacheGridAxis, 
-    y: CacheGridAxis,
+pub struct CacheGridData {
+    pub(super) x: CacheGridAxis, 
+    pub(super) y: CacheGridAxis,
 }
 
 impl CacheGridData {
@@ -44,81 +51,360 @@ impl CacheGridData {
     }
 }
 
-pub struct CacheGrid {
+/// Cells are shared with snapshots and copied on write, see [CacheGrid::snapshot]
+type CacheGridCell<K> = Arc<StableHashSet<K>>;
+
+/// Maps are also shared with snapshots, and copied on the first write after one
+type CacheGridEntities<K> = Arc<StableHashMap<K, CacheGridData>>;
+type CacheGridCells<K>    = Arc<StableHashMap<CacheGridKey, CacheGridCell<K>>>;
+
+/// Flat storage for the cells within a fixed rectangle, laid out column-major
+/// so iterating a query's y-range walks contiguous memory.
+struct CacheGridDense<K> {
+    x: CacheGridAxis,
+    y: CacheGridAxis,
+    cells: Vec<CacheGridCell<K>>,
+}
+
+impl<K> CacheGridDense<K> {
+    fn index(&self, CacheGridKey(x, y): CacheGridKey) -> Option<usize> {
+        if x < self.x.near || x >= self.x.far || y < self.y.near || y >= self.y.far { return None; }
+        Some(((x - self.x.near) as usize)*((self.y.far - self.y.near) as usize) + ((y - self.y.near) as usize))
+    }
+}
+
+/// A spatial hash of uniformly sized cells, tracking which cells the bounds of
+/// each key occupy. Keys are usually entities, but may be any small copyable
+/// identifier, ie. indices into an external collection.
+/// 
+/// Internal storage uses fixed hashers, so iteration order is only dependent
+/// on the sequence of operations performed, and is reproduced after a restore.
+pub struct CacheGrid<K: Copy + Eq + Hash = Entity> {
     scale: f32,
-    entities: HashMap<Entity, CacheGridData>,
-    cells:    HashMap<CacheGridKey, HashSet<Entity>>,
-    freelist: VecDeque<HashSet<Entity>>
+    entities: CacheGridEntities<K>,
+    dense:    Option<CacheGridDense<K>>,
+    cells:    CacheGridCells<K>,
+    freelist: VecDeque<StableHashSet<K>>,
+
+    // Diagnostics, atomic so queries only require shared access
+    queries:    AtomicUsize,
+    candidates: AtomicUsize,
+}
+
+/// A saved copy of the contents of a [CacheGrid], maps and cells are shared
+/// with the grid until modified so taking a snapshot each frame is affordable.
+/// Only the dense cell array is copied, and only as pointers.
+#[derive(Clone)]
+pub struct CacheGridSnapshot<K: Copy + Eq + Hash = Entity> {
+    entities: CacheGridEntities<K>,
+    dense:    Vec<CacheGridCell<K>>,
+    cells:    CacheGridCells<K>,
 }
 
-impl CacheGrid {
+impl<K: Copy + Eq + Hash> CacheGrid<K> {
 
     pub fn new(scale: f32) -> Self {
         Self{
             scale,
             entities: Default::default(),
+            dense:    None,
             cells:    Default::default(),
             freelist: Default::default(),
+            queries:    Default::default(),
+            candidates: Default::default(),
         }
     }
 
-    pub fn query(&mut self, x: Projection, y: Projection) -> HashSet<Entity> {
-        let data = CacheGridData::from((x, y), self.scale);
+    /// Creates a grid where cells within the given bounds are stored in a flat
+    /// array rather than hashed, cells outside of the bounds are still hashed.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `scale` The scale of the grid, see [Self::new]
+    /// * `x` The bounds of the dense region along the x-axis, in world units
+    /// * `y` The bounds of the dense region along the y-axis, in world units
+    pub fn new_bounded(scale: f32, x: Projection, y: Projection) -> Self {
+        let data = CacheGridData::from((x, y), scale);
+        let len  = ((data.x.far - data.x.near) as usize)*((data.y.far - data.y.near) as usize);
+        Self{
+            dense: Some(CacheGridDense{
+                x: data.x,
+                y: data.y,
+                cells: (0..len).map(|_| Default::default()).collect(),
+            }),
+            ..Self::new(scale)
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Calculates statistics describing the grid's occupancy, see [CacheGridStats]
+    pub fn stats(&self) -> CacheGridStats {
+        let mut stats = CacheGridStats{
+            entities:   self.entities.len(),
+            freelist:   self.freelist.len(),
+            queries:    self.queries.load(Ordering::Relaxed),
+            candidates: self.candidates.load(Ordering::Relaxed),
+            ..Default::default()
+        };
+
+        for cell in self.dense.iter().flat_map(|dense| dense.cells.iter()).chain(self.cells.values()) {
+            if cell.is_empty() { continue; }
+            stats.occupied_cells += 1;
+            stats.cell_entries   += cell.len();
+            stats.cell_histogram[cell.len().min(CACHE_GRID_HISTOGRAM_LEN - 1)] += 1;
+        }
 
+        stats
+    }
+
+    /// Resets the query counters reported by [Self::stats]
+    pub fn clear_query_counts(&self) {
+        self.queries.store(0, Ordering::Relaxed);
+        self.candidates.store(0, Ordering::Relaxed);
+    }
+
+    /// Saves the current contents of the grid, see [CacheGridSnapshot]
+    pub fn snapshot(&self) -> CacheGridSnapshot<K> {
+        CacheGridSnapshot{
+            entities: self.entities.clone(),
+            dense:    self.dense.as_ref().map(|dense| dense.cells.clone()).unwrap_or_default(),
+            cells:    self.cells.clone(),
+        }
+    }
+
+    /// Restores the contents of the grid from a snapshot taken from this grid
+    pub fn restore(&mut self, snapshot: &CacheGridSnapshot<K>) {
+        self.entities.clone_from(&snapshot.entities);
+        if let Some(dense) = &mut self.dense {
+            dense.cells.clone_from(&snapshot.dense);
+        }
+        self.cells.clone_from(&snapshot.cells);
+    }
+
+    pub fn query(&self, x: Projection, y: Projection) -> HashSet<K> {
+        let mut result: HashSet<K> = Default::default();
+        self.query_into(x, y, &mut result);
+        result
+    }
 
-        let mut result: HashSet<Entity> = Default::default();
+    pub fn query_into(&self, x: Projection, y: Projection, result: &mut HashSet<K>) {
+        let candidates = self.collect_into(x, y, result);
+        self.record_query(candidates);
+    }
+
+    /// Like [Self::query_into] but not counted by [Self::stats], for queries made
+    /// internally rather than on behalf of the user. Returns the candidates visited.
+    pub(super) fn collect_into(&self, x: Projection, y: Projection, result: &mut HashSet<K>) -> usize {
+        let data = CacheGridData::from((x, y), self.scale);
+        let mut candidates = 0;
         for x in data.x.near..data.x.far {
             for y in data.y.near..data.y.far {
-                let key = CacheGridKey(x, y);
-                if let Some(cell) = self.cells.get(&key) {
+                if let Some(cell) = self.cell(CacheGridKey(x, y)) {
+                    candidates += cell.len();
                     result.extend(cell);
                 }
             }
         }
+        candidates
+    }
 
-        result
+    /// Iterates over the cell-aligned bounds occupied by each key
+    pub fn iter_bounds(&self) -> impl Iterator<Item = (K, Projection, Projection)> + '_ {
+        self.entities.iter().map(|(&id, data)| (
+            id,
+            data.x.into_projection(self.scale),
+            data.y.into_projection(self.scale),
+        ))
+    }
+
+    /// Iterates the bounds of each occupied cell, along with the number of keys in it
+    pub fn iter_cells(&self) -> impl Iterator<Item = (Projection, Projection, usize)> + '_ {
+        let dense = self.dense.iter().flat_map(|dense| {
+            let height = (dense.y.far - dense.y.near) as usize;
+            dense.cells.iter().enumerate().map(move |(index, cell)| {
+                (CacheGridKey(dense.x.near + (index/height) as i32, dense.y.near + (index%height) as i32), cell)
+            })
+        });
+        dense.chain(self.cells.iter().map(|(&key, cell)| (key, cell)))
+            .filter(|(_, cell)| !cell.is_empty())
+            .map(|(CacheGridKey(x, y), cell)| (
+                CacheGridAxis{ near: x, far: x + 1 }.into_projection(self.scale),
+                CacheGridAxis{ near: y, far: y + 1 }.into_projection(self.scale),
+                cell.len(),
+            ))
+    }
+
+    pub fn update(&mut self, id: K, shape: Shape) {
+        let (x, y) = shape.project_aligned();
+        self.update_bounds(id, x, y);
     }
 
-    pub fn update(&mut self, entity: Entity, shape: Shape) {
-        let data_new = CacheGridData::from(shape.project_aligned(), self.scale);
+    /// Inserts or updates the key using axis-aligned bounds, rather than a shape
+    pub fn update_bounds(&mut self, id: K, x: Projection, y: Projection) {
+        self.update_prepared(id, CacheGridData::from((x, y), self.scale));
+    }
+
+    /// Calculates the cells a shape would occupy. This only requires shared access,
+    /// so may be performed in parallel ahead of [Self::insert_many].
+    pub fn prepare(&self, shape: Shape) -> CacheGridData {
+        CacheGridData::from(shape.project_aligned(), self.scale)
+    }
 
-        if let Some(&data_old) = self.entities.get(&entity) {
+    /// Inserts or updates the key using cells calculated by [Self::prepare]
+    pub fn update_prepared(&mut self, id: K, data_new: CacheGridData) {
+        if let Some(&data_old) = self.entities.get(&id) {
             // No change to occupancy
             if data_new == data_old { return; }
-            self.remove(entity);
+            self.remove(id);
         }
 
-        self.entities.insert(entity, data_new);
-        for x in data_new.x.near..data_new.x.far {
-            for y in data_new.y.near..data_new.y.far {
-                match self.cells.entry(CacheGridKey(x, y)) {
-                    Entry::Occupied(mut v) => { v.get_mut().insert(entity); },
-                    Entry::Vacant(v)       => { v.insert(self.freelist.pop_back().unwrap_or_default()).insert(entity); },
-                }
+        self.insert_impl(id, data_new);
+    }
+
+    /// Inserts or updates many keys using cells calculated by [Self::prepare]. All
+    /// stale occupancy is removed before any insertions, so emptied cells are
+    /// recycled rather than reallocated. Only the last entry of a repeated key
+    /// is inserted.
+    pub fn insert_many<I: IntoIterator<Item = (K, CacheGridData)>>(&mut self, items: I) {
+        let items: Vec<(K, CacheGridData)> = last_per_key(items).into_iter()
+            .filter(|(id, data)| self.entities.get(id) != Some(data))
+            .collect();
+
+        for &(id, _) in &items {
+            self.remove(id);
+        }
+
+        for (id, data) in items {
+            self.insert_impl(id, data);
+        }
+    }
+
+    pub fn remove(&mut self, id: K) {
+        if let Some(&data_old) = self.entities.get(&id) {
+            self.remove_impl(id, data_old);
+        }
+    }
+
+    fn insert_impl(&mut self, id: K, data: CacheGridData) {
+        Arc::make_mut(&mut self.entities).insert(id, data);
+        for x in data.x.near..data.x.far {
+            for y in data.y.near..data.y.far {
+                self.insert_cell(CacheGridKey(x, y), id);
+            }
+        }
+    }
+
+    fn remove_impl(&mut self, id: K, data: CacheGridData) {
+
+        Arc::make_mut(&mut self.entities).remove(&id);
+        for x in data.x.near..data.x.far {
+            for y in data.y.near..data.y.far {
+                self.remove_cell(CacheGridKey(x, y), id);
             }
         }
 
     }
 
-    pub fn remove(&mut self, entity: Entity) {
-        if let Some(&data_old) = self.entities.get(&entity) {
-            self.remove_impl(entity, data_old);
+    fn record_query(&self, candidates: usize) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        self.candidates.fetch_add(candidates, Ordering::Relaxed);
+    }
+
+    fn cell(&self, key: CacheGridKey) -> Option<&StableHashSet<K>> {
+        match self.dense.as_ref().and_then(|dense| dense.index(key).map(|index| &dense.cells[index])) {
+            Some(cell) => Some(cell),
+            None       => self.cells.get(&key).map(|cell| &**cell),
         }
     }
 
-    fn remove_impl(&mut self, entity: Entity, data: CacheGridData) {
+    fn insert_cell(&mut self, key: CacheGridKey, id: K) {
+        if let Some(dense) = &mut self.dense {
+            if let Some(index) = dense.index(key) {
+                Arc::make_mut(&mut dense.cells[index]).insert(id);
+                return;
+            }
+        }
+
+        match Arc::make_mut(&mut self.cells).entry(key) {
+            Entry::Occupied(mut v) => { Arc::make_mut(v.get_mut()).insert(id); },
+            Entry::Vacant(v)       => {
+                let mut cell = self.freelist.pop_back().unwrap_or_default();
+                cell.insert(id);
+                v.insert(Arc::new(cell));
+            },
+        }
+    }
+
+    fn remove_cell(&mut self, key: CacheGridKey, id: K) {
+        if let Some(dense) = &mut self.dense {
+            if let Some(index) = dense.index(key) {
+                Arc::make_mut(&mut dense.cells[index]).remove(&id);
+                return;
+            }
+        }
+
+        let cells = Arc::make_mut(&mut self.cells);
+        let cell = Arc::make_mut(cells.get_mut(&key).unwrap());
+        cell.remove(&id);
+        if cell.is_empty() {
+            // Unique after `make_mut`, so this only fails if that changes
+            if let Ok(cell) = Arc::try_unwrap(cells.remove(&key).unwrap()) {
+                self.freelist.push_back(cell);
+            }
+        }
+    }
+}
 
-        self.entities.remove(&entity);
+impl<K: Copy + Eq + Hash + Ord> CacheGrid<K> {
+    /// Like [Self::query], but sorted so iteration is deterministic
+    pub fn query_sorted(&self, x: Projection, y: Projection) -> Vec<K> {
+        let mut result = Vec::<K>::new();
+        self.query_sorted_into(x, y, &mut result);
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Appends the contents of the cells overlapping the bounds, unsorted and
+    /// containing duplicates, see [Self::query_sorted]
+    pub(super) fn query_sorted_into(&self, x: Projection, y: Projection, result: &mut Vec<K>) {
+        let data = CacheGridData::from((x, y), self.scale);
+        let len  = result.len();
         for x in data.x.near..data.x.far {
             for y in data.y.near..data.y.far {
-                let key = CacheGridKey(x, y);
-                let cell = self.cells.get_mut(&key).unwrap();
-                cell.remove(&entity);
-                if cell.is_empty() {
-                    self.freelist.push_back(self.cells.remove(&key).unwrap());
+                if let Some(cell) = self.cell(CacheGridKey(x, y)) {
+                    result.extend(cell.iter());
                 }
             }
         }
+        self.record_query(result.len() - len);
+    }
 
+    /// Collects every pair of keys sharing at least one cell, ordered
+    /// such that the first key of the pair is the lesser.
+    pub fn query_pairs_into(&self, result: &mut HashSet<(K, K)>) {
+        let mut cell_ids = Vec::<K>::new();
+        for cell in self.dense.iter().flat_map(|dense| dense.cells.iter()).chain(self.cells.values()) {
+            cell_ids.clear();
+            cell_ids.extend(cell.iter());
+            for (i, &a) in cell_ids.iter().enumerate() {
+                for &b in &cell_ids[i+1..] {
+                    result.insert(if a < b { (a, b) } else { (b, a) });
+                }
+            }
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Removes all but the last entry of each key, keeping their order
+pub(super) fn last_per_key<K: Copy + Eq + Hash, V, I: IntoIterator<Item = (K, V)>>(items: I) -> Vec<(K, V)> {
+    let mut seen = HashSet::default();
+    let mut result: Vec<(K, V)> = items.into_iter().collect();
+    result.reverse();
+    result.retain(|(id, _)| seen.insert(*id));
+    result.reverse();
+    result
+}
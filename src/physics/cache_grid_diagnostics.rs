@@ -0,0 +1,155 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::{
+    app::{App, Plugin, CoreStage},
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    ecs::system::{Res, ResMut},
+};
+
+use super::ColliderLookup;
+
+/// The number of buckets in [CacheGridStats::cell_histogram], the final
+/// bucket counts all cells with at least that many entities.
+pub const CACHE_GRID_HISTOGRAM_LEN: usize = 16;
+
+/// Statistics describing the occupancy of a [super::CacheGrid], useful for
+/// tuning the grid scale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheGridStats {
+    /// The number of entities stored in the grid
+    pub entities: usize,
+    /// The number of cells containing at least one entity
+    pub occupied_cells: usize,
+    /// The number of occupied cells by entity count, index `i` counts cells
+    /// holding `i` entities.
+    pub cell_histogram: [usize; CACHE_GRID_HISTOGRAM_LEN],
+    /// The total number of cells occupied by all entities
+    pub cell_entries: usize,
+    /// The number of empty cells waiting for reuse
+    pub freelist: usize,
+    /// The number of queries performed since the counters were last cleared
+    pub queries: usize,
+    /// The number of cell entries visited by queries since the counters were
+    /// last cleared, before duplicates are removed.
+    pub candidates: usize,
+}
+
+impl Default for CacheGridStats {
+    fn default() -> Self {
+        Self{
+            entities: 0,
+            occupied_cells: 0,
+            cell_histogram: [0; CACHE_GRID_HISTOGRAM_LEN],
+            cell_entries: 0,
+            freelist: 0,
+            queries: 0,
+            candidates: 0,
+        }
+    }
+}
+
+impl CacheGridStats {
+    /// The average number of cells each entity occupies
+    pub fn cells_per_entity(&self) -> f32 {
+        if self.entities == 0 { 0.0 } else { self.cell_entries as f32/self.entities as f32 }
+    }
+
+    /// The average number of entities in each occupied cell
+    pub fn entities_per_cell(&self) -> f32 {
+        if self.occupied_cells == 0 { 0.0 } else { self.cell_entries as f32/self.occupied_cells as f32 }
+    }
+
+    /// The average number of candidates visited per query
+    pub fn candidates_per_query(&self) -> f32 {
+        if self.queries == 0 { 0.0 } else { self.candidates as f32/self.queries as f32 }
+    }
+
+    /// Merges the statistics of two grids, ie. the levels of a [super::CacheGridHierarchy].
+    /// Every query is seen by every level, so the larger query count is kept.
+    pub fn combine(self, other: Self) -> Self {
+        let mut cell_histogram = self.cell_histogram;
+        cell_histogram.iter_mut().zip(other.cell_histogram).for_each(|(a, b)| *a += b);
+        Self{
+            entities:       self.entities       + other.entities,
+            occupied_cells: self.occupied_cells + other.occupied_cells,
+            cell_histogram,
+            cell_entries:   self.cell_entries   + other.cell_entries,
+            freelist:       self.freelist       + other.freelist,
+            queries:        self.queries.max(other.queries),
+            candidates:     self.candidates     + other.candidates,
+        }
+    }
+
+    /// Adds the statistics as measurements to the diagnostics registered by
+    /// [CacheGridDiagnosticsPlugin]
+    pub fn publish(&self, diagnostics: &mut Diagnostics) {
+        use CacheGridDiagnosticsPlugin as P;
+        diagnostics.add_measurement(P::ENTITIES,             self.entities       as f64);
+        diagnostics.add_measurement(P::OCCUPIED_CELLS,       self.occupied_cells as f64);
+        diagnostics.add_measurement(P::CELLS_PER_ENTITY,     self.cells_per_entity()     as f64);
+        diagnostics.add_measurement(P::ENTITIES_PER_CELL,    self.entities_per_cell()    as f64);
+        diagnostics.add_measurement(P::FREELIST,             self.freelist       as f64);
+        diagnostics.add_measurement(P::QUERIES,              self.queries        as f64);
+        diagnostics.add_measurement(P::CANDIDATES_PER_QUERY, self.candidates_per_query() as f64);
+        for (&id, &count) in P::CELL_HISTOGRAM.iter().zip(&self.cell_histogram) {
+            diagnostics.add_measurement(id, count as f64);
+        }
+    }
+}
+
+/// Registers diagnostics for [CacheGridStats], and publishes the statistics of
+/// the [ColliderLookup] resource added by [super::PhysicsPlugin] every frame.
+/// Other grids are published by calling [CacheGridStats::publish].
+#[derive(Default, Clone, Copy)]
+pub struct CacheGridDiagnosticsPlugin;
+
+impl Plugin for CacheGridDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(Self::setup_system)
+            .add_system_to_stage(CoreStage::Last, Self::publish_lookup_system);
+    }
+}
+
+impl CacheGridDiagnosticsPlugin {
+    pub const ENTITIES:             DiagnosticId = DiagnosticId::from_u128( 89778815574929524920353871826315136820);
+    pub const OCCUPIED_CELLS:       DiagnosticId = DiagnosticId::from_u128( 90159299105130606897586222245152569854);
+    pub const CELLS_PER_ENTITY:     DiagnosticId = DiagnosticId::from_u128(162412051731602171920238922862224144885);
+    pub const ENTITIES_PER_CELL:    DiagnosticId = DiagnosticId::from_u128(162568548273507215685921897205830464797);
+    pub const FREELIST:             DiagnosticId = DiagnosticId::from_u128( 99370738459204287550794611625967095608);
+    pub const QUERIES:              DiagnosticId = DiagnosticId::from_u128(101488139550085046339233187016753370693);
+    pub const CANDIDATES_PER_QUERY: DiagnosticId = DiagnosticId::from_u128( 85320388033812844976747512878121347237);
+    /// One per bucket of [CacheGridStats::cell_histogram]
+    pub const CELL_HISTOGRAM: [DiagnosticId; CACHE_GRID_HISTOGRAM_LEN] = {
+        let mut ids = [DiagnosticId::from_u128(0); CACHE_GRID_HISTOGRAM_LEN];
+        let mut i = 0;
+        while i < CACHE_GRID_HISTOGRAM_LEN {
+            ids[i] = DiagnosticId::from_u128(151072390154381722468914064231939735552 + i as u128);
+            i += 1;
+        }
+        ids
+    };
+
+    pub fn setup_system(mut diagnostics: ResMut<Diagnostics>) {
+        diagnostics.add(Diagnostic::new(Self::ENTITIES,             "cache_grid_entities",             20));
+        diagnostics.add(Diagnostic::new(Self::OCCUPIED_CELLS,       "cache_grid_occupied_cells",       20));
+        diagnostics.add(Diagnostic::new(Self::CELLS_PER_ENTITY,     "cache_grid_cells_per_entity",     20));
+        diagnostics.add(Diagnostic::new(Self::ENTITIES_PER_CELL,    "cache_grid_entities_per_cell",    20));
+        diagnostics.add(Diagnostic::new(Self::FREELIST,             "cache_grid_freelist",             20));
+        diagnostics.add(Diagnostic::new(Self::QUERIES,              "cache_grid_queries",              20));
+        diagnostics.add(Diagnostic::new(Self::CANDIDATES_PER_QUERY, "cache_grid_candidates_per_query", 20));
+        for (i, &id) in Self::CELL_HISTOGRAM.iter().enumerate() {
+            diagnostics.add(Diagnostic::new(id, format!("cache_grid_cells_holding_{}", i), 20));
+        }
+    }
+
+    /// Publishes the broadphase statistics of the [ColliderLookup] resource, if
+    /// present, then clears its query counters so they are counted per frame.
+    pub fn publish_lookup_system(lookup: Option<Res<ColliderLookup<'static>>>, mut diagnostics: ResMut<Diagnostics>) {
+        if let Some(lookup) = lookup {
+            lookup.broadphase().stats().publish(&mut diagnostics);
+            lookup.broadphase().clear_query_counts();
+        }
+    }
+}
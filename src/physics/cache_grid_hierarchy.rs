@@ -0,0 +1,194 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use std::hash::Hash;
+
+use bevy::{
+    ecs::entity::Entity,
+    utils::{HashSet, StableHashMap},
+};
+
+use crate::collision::{Shape, Projection};
+
+use super::{CacheGrid, CacheGridData, CacheGridSnapshot, CacheGridStats, last_per_key};
+
+/// A stack of [CacheGrid]s where each level's cells are twice the size of the
+/// level below it. Entities are placed into the finest level whose cells are
+/// at least as large as their bounds, so they only ever occupy a handful of
+/// cells regardless of their size. Queries walk every level.
+pub struct CacheGridHierarchy<K: Copy + Eq + Hash = Entity> {
+    levels:   Vec<CacheGrid<K>>,
+    entities: StableHashMap<K, usize>,
+}
+
+/// A saved copy of the contents of a [CacheGridHierarchy], see [CacheGridSnapshot]
+#[derive(Clone)]
+pub struct CacheGridHierarchySnapshot<K: Copy + Eq + Hash = Entity> {
+    levels:   Vec<CacheGridSnapshot<K>>,
+    entities: StableHashMap<K, usize>,
+}
+
+impl<K: Copy + Eq + Hash> CacheGridHierarchy<K> {
+
+    /// Creates a new hierarchy.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` The scale of the finest level, see [CacheGrid::new]
+    /// * `levels` The number of levels, must be > 0. Entities larger than the
+    ///   coarsest level's cells will span multiple cells of that level.
+    pub fn new(scale: f32, levels: usize) -> Self {
+        assert!(levels > 0, "CacheGridHierarchy must have at least one level");
+        Self{
+            levels:   (0..levels).map(|i| CacheGrid::new(scale/2.0f32.powi(i as i32))).collect(),
+            entities: Default::default(),
+        }
+    }
+
+    /// Creates a new hierarchy where each level stores the cells within the given
+    /// bounds densely, see [CacheGrid::new_bounded].
+    pub fn new_bounded(scale: f32, levels: usize, x: Projection, y: Projection) -> Self {
+        assert!(levels > 0, "CacheGridHierarchy must have at least one level");
+        Self{
+            levels:   (0..levels).map(|i| CacheGrid::new_bounded(scale/2.0f32.powi(i as i32), x, y)).collect(),
+            entities: Default::default(),
+        }
+    }
+
+    /// Calculates statistics for every level combined, see [CacheGridStats::combine]
+    pub fn stats(&self) -> CacheGridStats {
+        self.levels.iter().map(CacheGrid::stats).fold(Default::default(), CacheGridStats::combine)
+    }
+
+    /// Calculates statistics for each level individually, finest first
+    pub fn level_stats(&self) -> Vec<CacheGridStats> {
+        self.levels.iter().map(CacheGrid::stats).collect()
+    }
+
+    /// Resets the query counters of every level, see [CacheGrid::clear_query_counts]
+    pub fn clear_query_counts(&self) {
+        self.levels.iter().for_each(CacheGrid::clear_query_counts);
+    }
+
+    /// Saves the current contents of every level, see [CacheGrid::snapshot]
+    pub fn snapshot(&self) -> CacheGridHierarchySnapshot<K> {
+        CacheGridHierarchySnapshot{
+            levels:   self.levels.iter().map(CacheGrid::snapshot).collect(),
+            entities: self.entities.clone(),
+        }
+    }
+
+    /// Restores the contents of every level from a snapshot taken from this hierarchy
+    pub fn restore(&mut self, snapshot: &CacheGridHierarchySnapshot<K>) {
+        for (level, level_snapshot) in self.levels.iter_mut().zip(&snapshot.levels) {
+            level.restore(level_snapshot);
+        }
+        self.entities.clone_from(&snapshot.entities);
+    }
+
+    pub fn query(&self, x: Projection, y: Projection) -> HashSet<K> {
+        let mut result: HashSet<K> = Default::default();
+        for level in &self.levels {
+            level.query_into(x, y, &mut result);
+        }
+        result
+    }
+
+    /// Iterates over the cell-aligned bounds occupied by each key
+    pub fn iter_bounds(&self) -> impl Iterator<Item = (K, Projection, Projection)> + '_ {
+        self.levels.iter().flat_map(|level| level.iter_bounds())
+    }
+
+    /// Iterates the occupied cells of every level, see [CacheGrid::iter_cells]
+    pub fn iter_cells(&self) -> impl Iterator<Item = (Projection, Projection, usize)> + '_ {
+        self.levels.iter().flat_map(|level| level.iter_cells())
+    }
+
+    pub fn update(&mut self, id: K, shape: Shape) {
+        self.update_prepared(id, self.prepare(shape));
+    }
+
+    /// Calculates the level and cells a shape would occupy, see [CacheGrid::prepare]
+    pub fn prepare(&self, shape: Shape) -> (usize, CacheGridData) {
+        let level = self.level_for(shape);
+        (level, self.levels[level].prepare(shape))
+    }
+
+    /// Inserts or updates the key using cells calculated by [Self::prepare]
+    pub fn update_prepared(&mut self, id: K, (level, data): (usize, CacheGridData)) {
+        self.move_level(id, level);
+        self.levels[level].update_prepared(id, data);
+    }
+
+    /// Inserts or updates many keys using cells calculated by [Self::prepare],
+    /// batching the insertions per level, see [CacheGrid::insert_many]
+    pub fn insert_many<I: IntoIterator<Item = (K, (usize, CacheGridData))>>(&mut self, items: I) {
+        let mut batches = vec![Vec::<(K, CacheGridData)>::new(); self.levels.len()];
+        for (id, (level, data)) in last_per_key(items) {
+            self.move_level(id, level);
+            batches[level].push((id, data));
+        }
+
+        for (level, batch) in self.levels.iter_mut().zip(batches) {
+            level.insert_many(batch);
+        }
+    }
+
+    pub fn remove(&mut self, id: K) {
+        if let Some(level) = self.entities.remove(&id) {
+            self.levels[level].remove(id);
+        }
+    }
+
+    fn move_level(&mut self, id: K, level: usize) {
+        if let Some(level_old) = self.entities.insert(id, level) {
+            if level_old != level {
+                self.levels[level_old].remove(id);
+            }
+        }
+    }
+
+    fn level_for(&self, shape: Shape) -> usize {
+        // Cell size of level 0 is 1.0 in scaled units, doubling per level
+        let extent = 2.0*shape.hbound.max_element()*self.levels[0].scale();
+        let mut level = 0;
+        let mut cell  = 1.0;
+        while extent > cell && level + 1 < self.levels.len() {
+            level += 1;
+            cell  *= 2.0;
+        }
+        level
+    }
+}
+
+impl<K: Copy + Eq + Hash + Ord> CacheGridHierarchy<K> {
+    /// Like [Self::query], but sorted so iteration is deterministic
+    pub fn query_sorted(&self, x: Projection, y: Projection) -> Vec<K> {
+        let mut result = Vec::<K>::new();
+        for level in &self.levels {
+            level.query_sorted_into(x, y, &mut result);
+        }
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Collects every pair of keys sharing at least one cell, with pairs
+    /// spanning levels found by querying coarser levels with each key's cells.
+    pub fn query_pairs(&self) -> HashSet<(K, K)> {
+        let mut result:     HashSet<(K, K)> = Default::default();
+        let mut candidates: HashSet<K>      = Default::default();
+        for (i, level) in self.levels.iter().enumerate() {
+            level.query_pairs_into(&mut result);
+            for (id, x, y) in level.iter_bounds() {
+                candidates.clear();
+                for coarser in &self.levels[i+1..] {
+                    coarser.collect_into(x, y, &mut candidates);
+                }
+                result.extend(candidates.iter().map(|&other| if id < other { (id, other) } else { (other, id) }));
+            }
+        }
+        result
+    }
+}
@@ -3,12 +3,59 @@
 \*========================================================================*/
 
 use bevy::{
-    ecs::{component::Component}
+    app::{App, Plugin},
+    ecs::{component::Component, reflect::ReflectComponent},
+    reflect::Reflect,
 };
 
-use crate::collision::Shape;
+use crate::collision::{Shape, ShapeKind, InternalEdges};
 
-#[derive(Debug, Component)]
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Collider {
     pub shape: Shape,
 }
+
+/// Marks a [Collider] as never moving, storing it in a [super::StaticGrid]
+/// which is rebuilt whenever a static collider is added, changed or removed.
+#[derive(Debug, Default, Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct StaticCollider;
+
+/// The [InternalEdges] of a [Collider], which resolution never pushes through,
+/// see [super::Tilemap::colliders]
+#[derive(Debug, Default, Clone, Component)]
+pub struct ColliderEdges(pub InternalEdges);
+
+/// Assigns a [Collider] to an independent simulation, each space is tracked by
+/// its own [super::ColliderLookup] so queries never return colliders from other
+/// spaces. Colliders without this component belong to the default space.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Component, Reflect)]
+#[reflect(Component, PartialEq, Hash)]
+pub struct ColliderSpace(pub u32);
+
+impl ColliderSpace {
+    /// The space of colliders without a [ColliderSpace] component
+    pub const DEFAULT: Self = Self(0);
+
+    /// Resolves an optional component to the space it represents
+    pub fn of(space: Option<&ColliderSpace>) -> Self {
+        space.copied().unwrap_or_default()
+    }
+}
+
+/// Registers the collider components and shape types for reflection, so they
+/// can be saved in scenes and edited in inspectors.
+#[derive(Default, Clone, Copy)]
+pub struct ColliderReflectPlugin;
+
+impl Plugin for ColliderReflectPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Collider>()
+            .register_type::<StaticCollider>()
+            .register_type::<ColliderSpace>()
+            .register_type::<Shape>()
+            .register_type::<ShapeKind>();
+    }
+}
@@ -2,61 +2,348 @@
 ** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
 \*========================================================================*/
 
+use std::sync::Arc;
+
 use bevy::{
     ecs::{
         entity::Entity,
         world::World, 
-        query::{Changed, QueryState, Added, Or}
+        query::{Changed, QueryState, Added, Or, With, Without}
     },
+    tasks::ComputeTaskPool,
     utils::HashSet,
 };
 
 use crate::{
     math::Vec2,
-    collision::{Shape, Projection}
+    collision::{Shape, Projection, InternalEdges},
+    sync::QueueExclusiveMPSC,
 };
 
-use super::{Collider, CacheGrid};
+use super::{Collider, ColliderSpace, StaticCollider, StaticGrid, Broadphase, CacheGrid, CacheGridHierarchy, into_sorted};
 
 type QueryModifyState<'a> = QueryState<
-    (Entity, &'a Collider,), 
-    Or<(Added<Collider>, Changed<Collider>)>
+    (Entity, &'a Collider, Option<&'a ColliderSpace>), 
+    (Without<StaticCollider>, Or<(Added<Collider>, Changed<Collider>, Changed<ColliderSpace>)>)
+>;
+
+/// Number of changed colliders handled per task when updating in parallel
+const PARALLEL_BATCH_SIZE: usize = 256;
+
+/// Number of writer slots for collecting changes from parallel tasks
+const PARALLEL_WRITERS: usize = 16;
+
+type QueryStaticModifyState = QueryState<
+    Entity,
+    (With<StaticCollider>, With<Collider>, Or<(Added<Collider>, Changed<Collider>, Added<StaticCollider>, Changed<ColliderSpace>)>)
+>;
+
+type QueryStaticState<'a> = QueryState<
+    (Entity, &'a Collider, Option<&'a ColliderSpace>),
+    With<StaticCollider>
+>;
+
+type QueryColliderState<'a> = QueryState<
+    (&'a Collider, Option<&'a ColliderSpace>),
+    Without<StaticCollider>
 >;
 
-pub struct ColliderLookup<'a> {
+/// Tracks [Collider]s in the world for spatial queries. Dynamic colliders are
+/// kept in a [Broadphase], while colliders marked with [StaticCollider] are kept
+/// in a [StaticGrid]. Queries transparently combine both. Only colliders in
+/// the lookup's [ColliderSpace] are tracked.
+pub struct ColliderLookup<'a, B: Broadphase = CacheGrid> {
     query_modify: QueryModifyState<'a>,
-    cache_grid: CacheGrid,
+    query_static_modify: QueryStaticModifyState,
+    query_static: QueryStaticState<'a>,
+    query_collider: QueryColliderState<'a>,
+    space: ColliderSpace,
+    broadphase: B,
+    statics: Arc<StaticGrid>,
+    changes: QueueExclusiveMPSC<(Entity, Option<B::Prepared>), PARALLEL_WRITERS>,
+    pending: Vec<(Entity, Shape, bool)>,
 }
 
-impl<'a> ColliderLookup<'a> {
+impl<'a> ColliderLookup<'a, CacheGrid> {
     pub fn new(world: &mut World, scale: f32) -> Self {
+        Self::with_broadphase(world, CacheGrid::new(scale), scale)
+    }
+
+    /// Creates a lookup where cells within the given bounds are stored densely,
+    /// useful for levels with known extents, see [CacheGrid::new_bounded]
+    pub fn new_bounded(world: &mut World, scale: f32, x: Projection, y: Projection) -> Self {
+        Self::with_broadphase(world, CacheGrid::new_bounded(scale, x, y), scale)
+    }
+
+    /// Creates a lookup tracking only colliders in the given space
+    pub fn new_in_space(world: &mut World, scale: f32, space: ColliderSpace) -> Self {
+        Self::with_space(world, CacheGrid::new(scale), scale, space)
+    }
+}
+
+impl<'a> ColliderLookup<'a, CacheGridHierarchy> {
+    /// Creates a lookup backed by a multi-level grid, see [CacheGridHierarchy]
+    pub fn new_hierarchical(world: &mut World, scale: f32, levels: usize) -> Self {
+        Self::with_broadphase(world, CacheGridHierarchy::new(scale, levels), scale)
+    }
+}
+
+impl<'a, B: Broadphase> ColliderLookup<'a, B> {
+    /// Creates a lookup using the given broadphase for dynamic colliders.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `broadphase` The broadphase to store dynamic colliders in
+    /// * `static_scale` The scale of the grid for static colliders, see [StaticGrid]
+    pub fn with_broadphase(world: &mut World, broadphase: B, static_scale: f32) -> Self {
+        Self::with_space(world, broadphase, static_scale, ColliderSpace::DEFAULT)
+    }
+
+    /// Creates a lookup using the given broadphase, tracking only colliders in
+    /// the given space.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `broadphase` The broadphase to store dynamic colliders in
+    /// * `static_scale` The scale of the grid for static colliders, see [StaticGrid]
+    /// * `space` The space of colliders to track, see [ColliderSpace]
+    pub fn with_space(world: &mut World, broadphase: B, static_scale: f32, space: ColliderSpace) -> Self {
         Self{
             query_modify: world.query_filtered(),
-            cache_grid: CacheGrid::new(scale),
+            query_static_modify: world.query_filtered(),
+            query_static: world.query_filtered(),
+            query_collider: world.query_filtered(),
+            space,
+            broadphase,
+            statics: Arc::new(StaticGrid::new(static_scale)),
+            changes: Default::default(),
+            pending: Default::default(),
         }
     }
 
+    pub fn space(&self) -> ColliderSpace {
+        self.space
+    }
+
+    pub fn broadphase(&self) -> &B {
+        &self.broadphase
+    }
+
+    pub fn statics(&self) -> &StaticGrid {
+        &self.statics
+    }
+
+    /// Saves the contents of the lookup, ie. for rollback. The static grid is
+    /// shared with the snapshot rather than copied.
+    pub fn snapshot(&self) -> ColliderLookupSnapshot<B> {
+        ColliderLookupSnapshot{
+            broadphase: self.broadphase.snapshot(),
+            statics:    self.statics.clone(),
+        }
+    }
+
+    /// Restores the contents from a snapshot taken from this lookup. The world
+    /// should be restored to the matching state, otherwise changes made since
+    /// the snapshot won't be detected.
+    pub fn restore(&mut self, snapshot: &ColliderLookupSnapshot<B>) {
+        self.broadphase.restore(&snapshot.broadphase);
+        self.statics = snapshot.statics.clone();
+    }
+
     pub fn update(&mut self, world: &mut World) {
-        for (entity, collider,) in self.query_modify.iter(world) {
-            self.cache_grid.update(entity, collider.shape);
+        let mut rebuild = false;
+
+        for entity in self.query_static_modify.iter(world) {
+            // Might have been dynamic before gaining the marker
+            self.broadphase.remove(entity);
+            rebuild = true;
+        }
+
+        // Colliders which have left the space are removed rather than updated
+        let space = self.space;
+        match world.get_resource::<ComputeTaskPool>() {
+            Some(task_pool) => {
+                // Cell ranges are calculated in parallel, then applied in a single batch
+                self.pending.clear();
+                self.pending.extend(self.query_modify.iter(world).map(|(entity, collider, collider_space)| 
+                    (entity, collider.shape, ColliderSpace::of(collider_space) == space)
+                ));
+                let (changes, broadphase) = (&self.changes, &self.broadphase);
+                task_pool.scope(|scope| for batch in self.pending.chunks(PARALLEL_BATCH_SIZE) {
+                    scope.spawn(async move {
+                        // One writer per batch, so tasks only contend when starting
+                        let mut writer = changes.borrow_writer();
+                        for &(entity, shape, in_space) in batch {
+                            writer.insert((entity, in_space.then(|| broadphase.prepare(shape))));
+                        }
+                    });
+                });
+                self.broadphase.update_many(self.changes.iter().filter_map(|&(entity, prepared)| Some((entity, prepared?))));
+                for &(entity, prepared) in self.changes.iter() {
+                    if prepared.is_none() { self.broadphase.remove(entity); }
+                }
+                self.changes.clear();
+            },
+            None => for (entity, collider, collider_space) in self.query_modify.iter(world) {
+                if ColliderSpace::of(collider_space) == space {
+                    self.broadphase.update(entity, collider.shape);
+                } else {
+                    self.broadphase.remove(entity);
+                }
+            },
+        }
+
+        if self.update_removed_impl(world) || rebuild {
+            self.rebuild_statics(world);
+        }
+    }
+
+    /// Applies only the removals from the world. Bevy forgets removals at the
+    /// end of every frame, so this must run every frame [Self::update] doesn't,
+    /// see [super::PhysicsPlugin]. Removals are checked against the current
+    /// state of the world, so applying one twice does nothing.
+    pub fn update_removed(&mut self, world: &World) {
+        if self.update_removed_impl(world) {
+            self.rebuild_statics(world);
+        }
+    }
+
+    /// Returns true if the static grid needs rebuilding
+    fn update_removed_impl(&mut self, world: &World) -> bool {
+        let (space, mut rebuild) = (self.space, false);
+
+        for entity in world.removed::<StaticCollider>() {
+            rebuild = true;
+            if let Ok((collider, collider_space)) = self.query_collider.get(world, entity) {
+                if ColliderSpace::of(collider_space) == space {
+                    self.broadphase.update(entity, collider.shape);
+                }
+            }
+        }
+
+        for entity in world.removed::<ColliderSpace>() {
+            // Returned to the default space, statics are only rebuilt if one left or joined
+            match self.query_collider.get(world, entity) {
+                Ok((collider, collider_space)) if ColliderSpace::of(collider_space) == space => self.broadphase.update(entity, collider.shape),
+                Ok(_) => self.broadphase.remove(entity),
+                Err(_) if self.statics.contains(entity) => rebuild = true,
+                Err(_) if space == ColliderSpace::DEFAULT && self.query_static.get(world, entity).is_ok() => rebuild = true,
+                Err(_) => self.broadphase.remove(entity),
+            }
         }
 
         for entity in world.removed::<Collider>() {
             // TODO OPT move to Removed<Collider> when it's added
-            self.cache_grid.remove(entity);
+            if self.statics.contains(entity) {
+                rebuild = true;
+            } else if self.query_collider.get(world, entity).is_err() {
+                self.broadphase.remove(entity);
+            }
+        }
+        rebuild
+    }
+
+    fn rebuild_statics(&mut self, world: &World) {
+        let space = self.space;
+        let colliders = self.query_static.iter(world)
+            .filter(|&(_, _, collider_space)| ColliderSpace::of(collider_space) == space)
+            .map(|(entity, collider, _)| (entity, collider.shape));
+        match Arc::get_mut(&mut self.statics) {
+            Some(statics) => statics.rebuild(colliders),
+            None => {
+                // Shared with a snapshot, build a replacement instead
+                let mut statics = StaticGrid::new(self.statics.scale());
+                statics.rebuild(colliders);
+                self.statics = Arc::new(statics);
+            },
         }
     }
 
-    pub fn query(&mut self, shape: Shape) -> HashSet<Entity> {
+    pub fn query(&self, shape: Shape) -> HashSet<Entity> {
         let (x, y) = shape.project_aligned();
-        self.cache_grid.query(x, y)
+        self.query_bounds(x, y)
     }
 
-    pub fn query_motion(&mut self, shape: Shape, motion: Vec2) -> HashSet<Entity> {
+    pub fn query_motion(&self, shape: Shape, motion: Vec2) -> HashSet<Entity> {
         let (x, y) = shape.project_aligned();
-        self.cache_grid.query(
+        self.query_bounds(
             Projection::smear(x, motion.x), 
             Projection::smear(y, motion.y)
         )
     }
-}
\ No newline at end of file
+
+    /// Finds all pairs of colliders which may overlap, excluding pairs where
+    /// both colliders are static. The first entity of each pair is always the lesser.
+    pub fn query_pairs(&self) -> HashSet<(Entity, Entity)> {
+        let mut result = self.broadphase.query_pairs();
+        let mut candidates: HashSet<Entity> = Default::default();
+        self.broadphase.for_each_bounds(|entity, x, y| {
+            candidates.clear();
+            self.statics.query_into(x, y, &mut candidates);
+            result.extend(candidates.iter().map(|&other| if entity < other { (entity, other) } else { (other, entity) }));
+        });
+        result
+    }
+
+    /// Like [Self::query], but sorted by entity so iteration is deterministic
+    pub fn query_sorted(&self, shape: Shape) -> Vec<Entity> {
+        into_sorted(self.query(shape))
+    }
+
+    /// Like [Self::query_motion], but sorted by entity so iteration is deterministic
+    pub fn query_motion_sorted(&self, shape: Shape, motion: Vec2) -> Vec<Entity> {
+        into_sorted(self.query_motion(shape, motion))
+    }
+
+    /// Like [Self::query_pairs], but sorted by entities so iteration is deterministic
+    pub fn query_pairs_sorted(&self) -> Vec<(Entity, Entity)> {
+        into_sorted(self.query_pairs())
+    }
+
+    /// Pushes a shape out of every collider it overlaps, returning the total
+    /// translation applied. Colliders are resolved one at a time in entity
+    /// order, and never push through their [InternalEdges].
+    ///
+    /// # Arguments
+    ///
+    /// * `shape` The shape to resolve
+    /// * `collider` Gets the shape and internal edges of a candidate, if it has
+    ///   any, returning `None` to skip it, ie. for the entity being resolved
+    pub fn resolve<'e, F: FnMut(Entity) -> Option<(Shape, Option<&'e InternalEdges>)>>(&self, shape: Shape, collider: F) -> Vec2 {
+        self.resolve_hinted(shape, Vec2::ZERO, collider)
+    }
+
+    /// Like [Self::resolve], but ambiguous pushes undo `motion`, see
+    /// [Shape::get_penetration_hinted].
+    ///
+    /// # Arguments
+    ///
+    /// * `shape` The shape to resolve
+    /// * `motion` The velocity of the shape, or its change from the previous position
+    /// * `collider` Gets the shape and internal edges of a candidate, see [Self::resolve]
+    pub fn resolve_hinted<'e, F: FnMut(Entity) -> Option<(Shape, Option<&'e InternalEdges>)>>(&self, mut shape: Shape, motion: Vec2, mut collider: F) -> Vec2 {
+        let (origin, no_edges) = (shape.origin, InternalEdges::new());
+        for entity in self.query_sorted(shape) {
+            let (other, edges) = match collider(entity) {
+                Some(v) => v,
+                None    => continue,
+            };
+            if let Some(push) = shape.get_penetration_hinted(&other, edges.unwrap_or(&no_edges), motion) {
+                shape.origin += push;
+            }
+        }
+        shape.origin - origin
+    }
+
+    fn query_bounds(&self, x: Projection, y: Projection) -> HashSet<Entity> {
+        let mut result = self.broadphase.query(x, y);
+        self.statics.query_into(x, y, &mut result);
+        result
+    }
+}
+
+/// A saved copy of the contents of a [ColliderLookup], see [ColliderLookup::snapshot]
+pub struct ColliderLookupSnapshot<B: Broadphase = CacheGrid> {
+    broadphase: B::Snapshot,
+    statics:    Arc<StaticGrid>,
+}
@@ -0,0 +1,148 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use std::hash::Hash;
+
+use crate::{
+    math::Vec2,
+    collision::{Shape, ShapeKind, Projection},
+};
+
+use super::{CacheGrid, CacheGridHierarchy};
+
+/// A list of line segments for debug drawing, built without any rendering so
+/// the geometry can be inspected headlessly.
+#[derive(Debug, Default, Clone)]
+pub struct DebugLines {
+    lines: Vec<[Vec2; 2]>,
+}
+
+impl DebugLines {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn lines(&self) -> &[[Vec2; 2]] {
+        &self.lines
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// The vertices of every line, in pairs, as expected by a line list mesh
+    pub fn positions(&self) -> Vec<[f32; 3]> {
+        self.lines.iter().flat_map(|&[a, b]| [[a.x, a.y, 0.0], [b.x, b.y, 0.0]]).collect()
+    }
+
+    pub fn add_line(&mut self, a: Vec2, b: Vec2) {
+        self.lines.push([a, b]);
+    }
+
+    /// Adds lines joining each point to the next, and the last to the first
+    pub fn add_loop(&mut self, points: &[Vec2]) {
+        for (index, &point) in points.iter().enumerate() {
+            self.add_line(point, points[(index + 1) % points.len()]);
+        }
+    }
+
+    /// Adds the outline of a shape.
+    ///
+    /// # Arguments
+    ///
+    /// * `shape` The shape to outline
+    /// * `segments` The number of lines used to approximate ellipses
+    pub fn add_shape(&mut self, shape: &Shape, segments: usize) {
+        match shape.shape {
+            ShapeKind::Ellipse   => self.add_loop(&shape.get_points_ellipse(segments)),
+            ShapeKind::Rectangle => self.add_loop(&shape.get_points_bound()),
+            _                    => self.add_loop(&shape.get_points_slope()),
+        }
+    }
+
+    /// Adds the outline of an axis-aligned box
+    pub fn add_bounds(&mut self, x: Projection, y: Projection) {
+        self.add_loop(&[
+            Vec2::new(x.near(), y.near()),
+            Vec2::new(x.far(),  y.near()),
+            Vec2::new(x.far(),  y.far()),
+            Vec2::new(x.near(), y.far()),
+        ]);
+    }
+
+    /// Adds the outline of every occupied cell in a grid
+    pub fn add_cells<K: Copy + Eq + Hash>(&mut self, grid: &CacheGrid<K>) {
+        grid.iter_cells().for_each(|(x, y, _)| self.add_bounds(x, y));
+    }
+
+    /// Adds the outline of every occupied cell in every level of a grid
+    pub fn add_hierarchy_cells<K: Copy + Eq + Hash>(&mut self, grid: &CacheGridHierarchy<K>) {
+        grid.iter_cells().for_each(|(x, y, _)| self.add_bounds(x, y));
+    }
+
+    /// Adds a cross marking a contact point, with a line showing the push applied
+    ///
+    /// # Arguments
+    ///
+    /// * `point` The position of the contact
+    /// * `push` The translation applied to resolve the contact
+    /// * `size` The half-size of the cross
+    pub fn add_contact(&mut self, point: Vec2, push: Vec2, size: f32) {
+        self.add_line(point - Vec2::new(size, size), point + Vec2::new(size,  size));
+        self.add_line(point - Vec2::new(size, -size), point + Vec2::new(size, -size));
+        if push != Vec2::ZERO {
+            self.add_line(point, point + push);
+        }
+    }
+}
+
+/// A contact recorded for debug drawing, see [DebugContacts]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugContact {
+    pub point: Vec2,
+    pub push:  Vec2,
+    pub age:   f32,
+}
+
+/// Recently resolved contacts, kept for a short time so they can be drawn.
+/// Add contacts from resolution systems with [Self::add].
+#[derive(Debug, Clone)]
+pub struct DebugContacts {
+    pub lifetime: f32,
+    contacts: Vec<DebugContact>,
+}
+
+impl Default for DebugContacts {
+    fn default() -> Self {
+        Self{
+            lifetime: 0.5,
+            contacts: Default::default(),
+        }
+    }
+}
+
+impl DebugContacts {
+    pub fn add(&mut self, point: Vec2, push: Vec2) {
+        self.contacts.push(DebugContact{ point, push, age: 0.0 });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DebugContact> {
+        self.contacts.iter()
+    }
+
+    /// Ages every contact, forgetting those older than the lifetime
+    pub fn tick(&mut self, delta: f32) {
+        let lifetime = self.lifetime;
+        self.contacts.iter_mut().for_each(|contact| contact.age += delta);
+        self.contacts.retain(|contact| contact.age <= lifetime);
+    }
+}
@@ -0,0 +1,160 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::{
+    app::{App, Plugin, CoreStage},
+    asset::{Assets, Handle},
+    core::Time,
+    ecs::system::{Commands, Query, Res, ResMut},
+    render::{
+        color::Color,
+        mesh::Mesh,
+        render_resource::PrimitiveTopology,
+    },
+    sprite::{ColorMaterial, MaterialMesh2dBundle, Mesh2dHandle},
+    transform::components::Transform,
+};
+
+use super::{Collider, ColliderLookup, DebugLines, DebugContacts};
+
+/// Draws the outline of every [Collider], the occupied cells of the
+/// [ColliderLookup] resource and recent [DebugContacts] as 2D line meshes.
+#[derive(Debug, Clone, Copy)]
+pub struct ColliderDebugPlugin {
+    pub collider_color: Color,
+    pub cell_color:     Color,
+    pub contact_color:  Color,
+    /// The depth the lines are drawn at
+    pub depth: f32,
+}
+
+impl Default for ColliderDebugPlugin {
+    fn default() -> Self {
+        Self{
+            collider_color: Color::GREEN,
+            cell_color:     Color::rgba(1.0, 1.0, 1.0, 0.25),
+            contact_color:  Color::RED,
+            depth: 100.0,
+        }
+    }
+}
+
+/// Controls what [ColliderDebugPlugin] draws, can be changed at any time
+#[derive(Debug, Clone, Copy)]
+pub struct ColliderDebugSettings {
+    pub colliders: bool,
+    pub cells:     bool,
+    pub contacts:  bool,
+    /// The number of lines used to approximate ellipses
+    pub segments: usize,
+    /// The half-size of the cross marking each contact
+    pub contact_size: f32,
+}
+
+impl Default for ColliderDebugSettings {
+    fn default() -> Self {
+        Self{
+            colliders: true,
+            cells:     false,
+            contacts:  true,
+            segments:  24,
+            contact_size: 0.1,
+        }
+    }
+}
+
+/// The meshes updated by [ColliderDebugPlugin]
+pub struct ColliderDebugMeshes {
+    pub colliders: Handle<Mesh>,
+    pub cells:     Handle<Mesh>,
+    pub contacts:  Handle<Mesh>,
+}
+
+impl Plugin for ColliderDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(*self)
+            .init_resource::<ColliderDebugSettings>()
+            .init_resource::<DebugContacts>()
+            .add_startup_system(setup_system)
+            .add_system_to_stage(CoreStage::PostUpdate, update_system);
+    }
+}
+
+fn setup_system(
+    plugin: Res<ColliderDebugPlugin>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mut spawn = |color: Color| {
+        let mesh = meshes.add(lines_mesh(&DebugLines::new()));
+        commands.spawn_bundle(MaterialMesh2dBundle{
+            mesh: Mesh2dHandle(mesh.clone()),
+            material: materials.add(ColorMaterial::from(color)),
+            transform: Transform::from_xyz(0.0, 0.0, plugin.depth),
+            ..Default::default()
+        });
+        mesh
+    };
+
+    let handles = ColliderDebugMeshes{
+        colliders: spawn(plugin.collider_color),
+        cells:     spawn(plugin.cell_color),
+        contacts:  spawn(plugin.contact_color),
+    };
+    commands.insert_resource(handles);
+}
+
+fn update_system(
+    time: Res<Time>,
+    settings: Res<ColliderDebugSettings>,
+    handles: Option<Res<ColliderDebugMeshes>>,
+    lookup: Option<Res<ColliderLookup<'static>>>,
+    mut contacts: ResMut<DebugContacts>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    colliders: Query<&Collider>,
+) {
+    contacts.tick(time.delta_seconds());
+    let handles = crate::try_unwrap_option!(handles);
+
+    let mut lines = DebugLines::new();
+    if settings.colliders {
+        colliders.for_each(|collider| lines.add_shape(&collider.shape, settings.segments));
+    }
+    set_mesh(&mut meshes, &handles.colliders, &lines);
+
+    lines.clear();
+    if let (true, Some(lookup)) = (settings.cells, &lookup) {
+        lines.add_cells(lookup.broadphase());
+    }
+    set_mesh(&mut meshes, &handles.cells, &lines);
+
+    lines.clear();
+    if settings.contacts {
+        contacts.iter().for_each(|contact| lines.add_contact(contact.point, contact.push, settings.contact_size));
+    }
+    set_mesh(&mut meshes, &handles.contacts, &lines);
+}
+
+fn set_mesh(meshes: &mut Assets<Mesh>, handle: &Handle<Mesh>, lines: &DebugLines) {
+    if let Some(mesh) = meshes.get_mut(handle) {
+        *mesh = lines_mesh(lines);
+    }
+}
+
+/// Creates a line list mesh from the lines
+pub fn lines_mesh(lines: &DebugLines) -> Mesh {
+    let mut positions = lines.positions();
+    if positions.is_empty() {
+        // Avoid empty vertex buffers
+        positions.extend([[0.0; 3]; 2]);
+    }
+    let count = positions.len();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL,   vec![[0.0, 0.0, 1.0]; count]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0,     vec![[0.0, 0.0]; count]);
+    mesh
+}
@@ -2,6 +2,9 @@
 ** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
 \*========================================================================*/
 
+#[cfg(test)]
+mod test;
+
 mod collider;
 pub use collider::*;
 
@@ -9,4 +12,36 @@ mod collider_lookup;
 pub use collider_lookup::*;
 
 mod cache_grid;
-pub(crate) use cache_grid::*;
\ No newline at end of file
+pub use cache_grid::*;
+
+mod cache_grid_hierarchy;
+pub use cache_grid_hierarchy::*;
+
+mod cache_grid_diagnostics;
+pub use cache_grid_diagnostics::*;
+
+mod broadphase;
+pub use broadphase::*;
+
+mod sweep_and_prune;
+pub use sweep_and_prune::*;
+
+mod aabb_tree;
+pub use aabb_tree::*;
+
+mod static_grid;
+pub use static_grid::*;
+
+mod physics_plugin;
+pub use physics_plugin::*;
+
+mod tilemap;
+pub use tilemap::*;
+
+mod debug_lines;
+pub use debug_lines::*;
+
+#[cfg(feature = "render")]
+mod debug_render;
+#[cfg(feature = "render")]
+pub use debug_render::*;
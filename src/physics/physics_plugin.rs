@@ -0,0 +1,191 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::{
+    app::{App, Plugin, CoreStage},
+    core::{FixedTimestep, FixedTimesteps},
+    ecs::{
+        component::Component,
+        query::{Changed, With, Without},
+        schedule::{ExclusiveSystemDescriptorCoercion, IntoSystemDescriptor, ParallelSystemDescriptorCoercion, Schedule, StageLabel, SystemStage},
+        system::{IntoExclusiveSystem, Query, Res},
+        world::{Mut, World},
+    },
+    transform::{components::Transform, TransformSystem},
+};
+
+use crate::math::Vec2;
+
+use super::{Collider, ColliderEdges, ColliderLookup, StaticCollider};
+
+/// The label of the [FixedTimesteps] state driving [PhysicsPlugin]
+pub const PHYSICS_TIMESTEP: &str = "physics_timestep";
+
+/// The stage containing the fixed-rate physics schedule, see [PhysicsStage]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub struct PhysicsSchedule;
+
+/// The stages of a single physics step, run in order zero or more times a
+/// frame so each step always covers the same length of time.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub enum PhysicsStage {
+    /// Stores the positions from the previous step, for interpolation
+    Prepare,
+    /// Movement, changes [PhysicsPosition::current]
+    Step,
+    /// Moves colliders to their positions and updates the [ColliderLookup]
+    Lookup,
+    /// Collision resolution, queries the [ColliderLookup]
+    Resolve,
+}
+
+/// Marks an entity whose [PhysicsPosition] is pushed out of any [StaticCollider]
+/// it overlaps at the end of each step, see [resolve_static_system]
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct ResolveStatic;
+
+/// The position of an entity as of the latest physics step. The [Transform]
+/// is interpolated between the previous and current position every frame,
+/// smoothing out movement when the frame rate differs from the physics rate.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct PhysicsPosition {
+    pub current:  Vec2,
+    pub previous: Vec2,
+}
+
+impl PhysicsPosition {
+    pub fn new(position: Vec2) -> Self {
+        Self{
+            current:  position,
+            previous: position,
+        }
+    }
+
+    /// Moves to the position without interpolating from the old position
+    pub fn teleport(&mut self, position: Vec2) {
+        self.current  = position;
+        self.previous = position;
+    }
+
+    /// The position between the previous and current position
+    ///
+    /// # Arguments
+    ///
+    /// * `alpha` The progress towards the current position, from 0 to 1
+    pub fn interpolate(&self, alpha: f32) -> Vec2 {
+        self.previous.lerp(self.current, alpha)
+    }
+}
+
+/// Runs a [PhysicsSchedule] at a fixed rate after [CoreStage::Update], with a
+/// [ColliderLookup] resource for the default space. Systems are added to the
+/// stages of a step with [PhysicsAppExt::add_physics_system].
+#[derive(Debug, Clone, Copy)]
+pub struct PhysicsPlugin {
+    pub steps_per_second: f64,
+    pub scale: f32,
+}
+
+impl Default for PhysicsPlugin {
+    fn default() -> Self {
+        Self{
+            steps_per_second: 60.0,
+            scale: 1.0,
+        }
+    }
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        let lookup = ColliderLookup::<'static>::new(&mut app.world, self.scale);
+        app.insert_resource(lookup);
+
+        let schedule = Schedule::default()
+            .with_run_criteria(FixedTimestep::steps_per_second(self.steps_per_second).with_label(PHYSICS_TIMESTEP))
+            .with_stage(PhysicsStage::Prepare, SystemStage::parallel().with_system(store_previous_system))
+            .with_stage(PhysicsStage::Step,    SystemStage::parallel())
+            .with_stage(PhysicsStage::Lookup,  SystemStage::single_threaded()
+                .with_system(sync_collider_system)
+                .with_system(update_lookup_system.exclusive_system().at_end()))
+            .with_stage(PhysicsStage::Resolve, SystemStage::parallel().with_system(resolve_static_system));
+
+        app.add_stage_after(CoreStage::Update, PhysicsSchedule, schedule)
+            .add_system_to_stage(CoreStage::PostUpdate, interpolate_system.before(TransformSystem::TransformPropagate))
+            .add_system_to_stage(CoreStage::PostUpdate, update_lookup_removed_system.exclusive_system().at_end());
+    }
+}
+
+/// Adds systems to the stages of the [PhysicsSchedule]
+pub trait PhysicsAppExt {
+    fn add_physics_system<Params>(&mut self, stage: PhysicsStage, system: impl IntoSystemDescriptor<Params>) -> &mut Self;
+}
+
+impl PhysicsAppExt for App {
+    fn add_physics_system<Params>(&mut self, stage: PhysicsStage, system: impl IntoSystemDescriptor<Params>) -> &mut Self {
+        self.stage(PhysicsSchedule, |schedule: &mut Schedule| schedule.add_system_to_stage(stage, system))
+    }
+}
+
+pub fn store_previous_system(mut query: Query<&mut PhysicsPosition>) {
+    for mut position in query.iter_mut() {
+        // Avoid triggering change detection for entities that didn't move
+        if position.previous != position.current {
+            position.previous = position.current;
+        }
+    }
+}
+
+pub fn sync_collider_system(mut query: Query<(&PhysicsPosition, &mut Collider), Changed<PhysicsPosition>>) {
+    for (position, mut collider) in query.iter_mut() {
+        collider.shape.origin = position.current;
+    }
+}
+
+pub fn update_lookup_system(world: &mut World) {
+    world.resource_scope(|world, mut lookup: Mut<ColliderLookup<'static>>| {
+        lookup.update(world);
+    });
+}
+
+/// Applies removals to the lookup every frame, as frames without a physics step
+/// would otherwise lose them, see [ColliderLookup::update_removed]. Runs before
+/// Bevy clears removals at the start of [CoreStage::Last].
+pub fn update_lookup_removed_system(world: &mut World) {
+    world.resource_scope(|world, mut lookup: Mut<ColliderLookup<'static>>| {
+        lookup.update_removed(world);
+    });
+}
+
+type QueryStatics<'w, 's, 'a> = Query<'w, 's,
+    (&'a Collider, Option<&'a ColliderEdges>),
+    (With<StaticCollider>, Without<ResolveStatic>)
+>;
+
+pub fn resolve_static_system(
+    lookup:  Res<ColliderLookup<'static>>,
+    mut query: Query<(&mut PhysicsPosition, &mut Collider), With<ResolveStatic>>,
+    statics: QueryStatics,
+) {
+    for (mut position, mut collider) in query.iter_mut() {
+        let motion = position.current - position.previous;
+        let push = lookup.resolve_hinted(collider.shape, motion, |entity| {
+            let (other, edges) = statics.get(entity).ok()?;
+            Some((other.shape, edges.map(|edges| &edges.0)))
+        });
+        if push != Vec2::ZERO {
+            // The lookup catches up with the collider in the next step
+            position.current      += push;
+            collider.shape.origin += push;
+        }
+    }
+}
+
+pub fn interpolate_system(timesteps: Res<FixedTimesteps>, mut query: Query<(&PhysicsPosition, &mut Transform)>) {
+    let alpha = timesteps.get(PHYSICS_TIMESTEP).map_or(1.0, |state| state.overstep_percentage() as f32);
+    for (position, mut transform) in query.iter_mut() {
+        let position = position.interpolate(alpha);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
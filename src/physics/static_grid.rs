@@ -0,0 +1,110 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::{
+    ecs::entity::Entity,
+    utils::HashSet,
+};
+
+use crate::collision::{Shape, Projection};
+
+use super::CacheGridData;
+
+/// An immutable grid for colliders that never move, such as level geometry.
+/// Cells are stored as sorted, contiguous runs of entity indices rather than
+/// hashed sets, making queries cheap at the cost of a full rebuild whenever
+/// the contents change.
+pub struct StaticGrid {
+    scale: f32,
+    entities: Vec<(Entity, Projection, Projection)>,
+    keys:     Vec<(i32, i32)>,
+    offsets:  Vec<usize>,
+    items:    Vec<usize>,
+}
+
+impl StaticGrid {
+
+    pub fn new(scale: f32) -> Self {
+        Self{
+            scale,
+            entities: Default::default(),
+            keys:     Default::default(),
+            offsets:  vec![0],
+            items:    Default::default(),
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Replaces the contents of the grid with the given colliders
+    pub fn rebuild<I: IntoIterator<Item = (Entity, Shape)>>(&mut self, colliders: I) {
+        self.entities.clear();
+        self.keys.clear();
+        self.offsets.clear();
+        self.items.clear();
+
+        self.entities.extend(colliders.into_iter().map(|(entity, shape)| {
+            let (x, y) = shape.project_aligned();
+            (entity, x, y)
+        }));
+        self.entities.sort_unstable_by_key(|&(entity, _, _)| entity);
+
+        let mut cells = Vec::<((i32, i32), usize)>::new();
+        for (index, &(_, x, y)) in self.entities.iter().enumerate() {
+            let data = CacheGridData::from((x, y), self.scale);
+            for x in data.x.near..data.x.far {
+                for y in data.y.near..data.y.far {
+                    cells.push(((x, y), index));
+                }
+            }
+        }
+        cells.sort_unstable();
+
+        for (key, index) in cells {
+            if self.keys.last() != Some(&key) {
+                self.keys.push(key);
+                self.offsets.push(self.items.len());
+            }
+            self.items.push(index);
+        }
+        self.offsets.push(self.items.len());
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.binary_search_by_key(&entity, |&(entity, _, _)| entity).is_ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn query(&self, x: Projection, y: Projection) -> HashSet<Entity> {
+        let mut result: HashSet<Entity> = Default::default();
+        self.query_into(x, y, &mut result);
+        result
+    }
+
+    /// Collects entities whose bounds overlap the query, unlike [super::CacheGrid]
+    /// this is exact as the bounds are stored alongside the cells.
+    pub fn query_into(&self, x: Projection, y: Projection, result: &mut HashSet<Entity>) {
+        let data = CacheGridData::from((x, y), self.scale);
+        for cell_x in data.x.near..data.x.far {
+            // Keys are sorted by x then y, so each column is a contiguous run
+            let start = self.keys.partition_point(|&key| key < (cell_x, data.y.near));
+            let end   = self.keys.partition_point(|&key| key < (cell_x, data.y.far ));
+            for &index in &self.items[self.offsets[start]..self.offsets[end]] {
+                let (entity, entity_x, entity_y) = self.entities[index];
+                if !x.are_separate(entity_x) && !y.are_separate(entity_y) {
+                    result.insert(entity);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::{
+    ecs::entity::Entity,
+    utils::{HashSet, StableHashMap},
+};
+
+use crate::collision::{Shape, Projection};
+
+use super::Broadphase;
+
+/// A sort-and-sweep broadphase, keeping entities sorted by the near edge of
+/// their bounds along the x-axis. Cheap to maintain when entities are sparse
+/// or move coherently, but degrades when many entities share an x-range.
+#[derive(Default, Clone)]
+pub struct SweepAndPrune {
+    bounds: StableHashMap<Entity, (Projection, Projection)>,
+    sorted: Vec<(f32, Entity)>,
+    max_length: f32,
+}
+
+impl SweepAndPrune {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn remove_sorted(&mut self, entity: Entity, x: Projection) {
+        let start = self.sorted.partition_point(|&(near, _)| near < x.near());
+        let index = self.sorted[start..].iter().position(|&(_, v)| v == entity).unwrap();
+        self.sorted.remove(start + index);
+    }
+}
+
+impl Broadphase for SweepAndPrune {
+    type Prepared = (Projection, Projection);
+    type Snapshot = Self;
+
+    fn prepare(&self, shape: Shape) -> Self::Prepared {
+        shape.project_aligned()
+    }
+
+    fn update_prepared(&mut self, entity: Entity, (x, y): Self::Prepared) {
+        if let Some((x_old, _)) = self.bounds.insert(entity, (x, y)) {
+            self.remove_sorted(entity, x_old);
+        }
+
+        // Lengths are never shrunk on removal, this only widens the query window
+        self.max_length = self.max_length.max(x.length());
+        let index = self.sorted.partition_point(|&(near, _)| near <= x.near());
+        self.sorted.insert(index, (x.near(), entity));
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        if let Some((x, _)) = self.bounds.remove(&entity) {
+            self.remove_sorted(entity, x);
+        }
+    }
+
+    fn query(&self, x: Projection, y: Projection) -> HashSet<Entity> {
+        let start = self.sorted.partition_point(|&(near, _)| near <  x.near() - self.max_length);
+        let end   = self.sorted.partition_point(|&(near, _)| near <= x.far());
+        self.sorted[start..end.max(start)].iter().filter_map(|&(_, entity)| {
+            let (other_x, other_y) = self.bounds[&entity];
+            if x.are_separate(other_x) || y.are_separate(other_y) { None } else { Some(entity) }
+        }).collect()
+    }
+
+    fn query_pairs(&self) -> HashSet<(Entity, Entity)> {
+        let mut result: HashSet<(Entity, Entity)> = Default::default();
+        for (i, &(_, a)) in self.sorted.iter().enumerate() {
+            let (a_x, a_y) = self.bounds[&a];
+            for &(near, b) in &self.sorted[i+1..] {
+                if near > a_x.far() { break; }
+                if !a_y.are_separate(self.bounds[&b].1) {
+                    result.insert(if a < b { (a, b) } else { (b, a) });
+                }
+            }
+        }
+        result
+    }
+
+    fn for_each_bounds<F: FnMut(Entity, Projection, Projection)>(&self, mut func: F) {
+        self.bounds.iter().for_each(|(&entity, &(x, y))| func(entity, x, y));
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.clone()
+    }
+
+    fn restore(&mut self, snapshot: &Self::Snapshot) {
+        self.clone_from(snapshot);
+    }
+}
@@ -0,0 +1,648 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use bevy::{
+    app::App,
+    core::{FixedTimesteps, Time},
+    diagnostic::{Diagnostic, Diagnostics},
+    ecs::{schedule::{Schedule, Stage, SystemStage}, system::Query},
+    transform::components::Transform,
+    ecs::{entity::Entity, world::World},
+    tasks::{ComputeTaskPool, TaskPool},
+    utils::HashSet,
+};
+
+use crate::{
+    math::Vec2,
+    collision::{Shape, ShapeKind, Projection, Side},
+};
+
+use super::*;
+
+fn entity(id: u32) -> Entity {
+    Entity::from_raw(id)
+}
+
+fn query(grid: &CacheGridHierarchy, shape: Shape) -> Vec<Entity> {
+    let (x, y) = shape.project_aligned();
+    let mut result: Vec<Entity> = grid.query(x, y).into_iter().collect();
+    result.sort();
+    result
+}
+
+#[test]
+fn hierarchy_mixed_sizes() {
+    let mut grid = CacheGridHierarchy::new(1.0, 8);
+    grid.update(entity(0), Shape::square(Vec2::new(  0.5, 0.5),  0.25));
+    grid.update(entity(1), Shape::square(Vec2::new(  0.0, 0.0), 50.0 ));
+    grid.update(entity(2), Shape::square(Vec2::new(-30.5, 0.5),  0.25));
+
+    assert_eq!(query(&grid, Shape::square(Vec2::new(  0.5, 0.5), 0.1)), vec![entity(0), entity(1)]);
+    assert_eq!(query(&grid, Shape::square(Vec2::new(-30.5, 0.5), 0.1)), vec![entity(1), entity(2)]);
+    assert_eq!(query(&grid, Shape::square(Vec2::new(200.5, 0.5), 0.1)), vec![]);
+}
+
+#[test]
+fn hierarchy_update_between_levels() {
+    let mut grid = CacheGridHierarchy::new(1.0, 8);
+    grid.update(entity(0), Shape::square(Vec2::new(0.5, 0.5),  0.25));
+    grid.update(entity(0), Shape::square(Vec2::new(0.5, 0.5), 20.0 ));
+    assert_eq!(query(&grid, Shape::square(Vec2::new(15.5, 15.5), 0.1)), vec![entity(0)]);
+
+    grid.update(entity(0), Shape::square(Vec2::new(0.5, 0.5),  0.25));
+    assert_eq!(query(&grid, Shape::square(Vec2::new(15.5, 15.5), 0.1)), vec![]);
+    assert_eq!(query(&grid, Shape::square(Vec2::new( 0.5,  0.5), 0.1)), vec![entity(0)]);
+
+    grid.remove(entity(0));
+    assert_eq!(query(&grid, Shape::square(Vec2::new( 0.5,  0.5), 0.1)), vec![]);
+}
+
+#[test]
+fn insert_many_repeated_key() {
+    // Only the last entry counts, leaving nothing behind from the first
+    let (near, far) = (Shape::square(Vec2::new(0.5, 0.5), 0.25), Shape::square(Vec2::new(10.5, 0.5), 0.25));
+    let mut grid = CacheGrid::new(1.0);
+    grid.insert_many([(entity(0), grid.prepare(near)), (entity(0), grid.prepare(far))]);
+    assert_eq!(grid.iter_cells().count(), 1);
+    assert!(grid.query(far.project_aligned().0, far.project_aligned().1).contains(&entity(0)));
+
+    let mut hierarchy = CacheGridHierarchy::new(1.0, 8);
+    let large = Shape::square(Vec2::new(0.5, 0.5), 20.0);
+    hierarchy.insert_many([(entity(0), hierarchy.prepare(large)), (entity(0), hierarchy.prepare(near))]);
+    assert_eq!(query(&hierarchy, Shape::square(Vec2::new(15.5, 15.5), 0.1)), vec![]);
+    assert_eq!(query(&hierarchy, Shape::square(Vec2::new( 0.5,  0.5), 0.1)), vec![entity(0)]);
+}
+
+fn scatter(count: u32) -> Vec<(Entity, Shape)> {
+    // Simple LCG, we only need a repeatable spread of positions/sizes
+    let mut state = 0x2545_F491u32;
+    let mut next = move || {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (state >> 8) as f32 / (1 << 24) as f32
+    };
+    (0..count).map(|i| {
+        let origin = Vec2::new(next()*200.0 - 100.0, next()*200.0 - 100.0);
+        let size   = Vec2::new(next()*4.0 + 0.1, next()*4.0 + 0.1) * if i % 17 == 0 { 10.0 } else { 1.0 };
+        (entity(i), Shape::rectangle(origin, size))
+    }).collect()
+}
+
+fn exact_pairs(shapes: &[(Entity, Shape)]) -> HashSet<(Entity, Entity)> {
+    let mut result: HashSet<(Entity, Entity)> = Default::default();
+    for (i, &(a, a_shape)) in shapes.iter().enumerate() {
+        let (a_x, a_y) = a_shape.project_aligned();
+        for &(b, b_shape) in &shapes[i+1..] {
+            let (b_x, b_y) = b_shape.project_aligned();
+            if !a_x.are_separate(b_x) && !a_y.are_separate(b_y) {
+                result.insert(if a < b { (a, b) } else { (b, a) });
+            }
+        }
+    }
+    result
+}
+
+fn check_broadphase<B: Broadphase>(mut broadphase: B, exact: bool) {
+    let mut shapes = scatter(300);
+    for &(entity, shape) in &shapes { broadphase.update(entity, shape); }
+
+    // Move some, remove some
+    for (entity, shape) in shapes.iter_mut().step_by(3) {
+        shape.origin += Vec2::new(1.5, -0.5);
+        broadphase.update(*entity, *shape);
+    }
+    for &(entity, _) in shapes.iter().step_by(5) { broadphase.remove(entity); }
+    shapes = shapes.into_iter().enumerate().filter(|(i, _)| i % 5 != 0).map(|(_, v)| v).collect();
+
+    let expected = exact_pairs(&shapes);
+    let pairs    = broadphase.query_pairs();
+    assert!(expected.is_subset(&pairs), "Broadphase missed pairs");
+    if exact { assert_eq!(expected, pairs); }
+
+    for &(entity, shape) in &shapes {
+        let (x, y) = shape.project_aligned();
+        assert!(broadphase.query(x, y).contains(&entity), "Broadphase missed entity");
+    }
+}
+
+#[test]
+fn broadphase_cache_grid() {
+    check_broadphase(CacheGrid::new(0.25), false);
+}
+
+#[test]
+fn broadphase_cache_grid_hierarchy() {
+    check_broadphase(CacheGridHierarchy::new(1.0, 6), false);
+}
+
+#[test]
+fn broadphase_sweep_and_prune() {
+    check_broadphase(SweepAndPrune::new(), true);
+}
+
+#[test]
+fn broadphase_aabb_tree() {
+    check_broadphase(AabbTree::new(0.5), true);
+}
+
+#[test]
+fn static_grid_query() {
+    let mut grid = StaticGrid::new(0.5);
+    grid.rebuild([
+        (entity(2), Shape::rectangle(Vec2::new(  0.0, 0.0), Vec2::new(10.0, 1.0))),
+        (entity(0), Shape::square(   Vec2::new(-20.0, 0.0), 1.0)),
+        (entity(1), Shape::square(   Vec2::new(  5.0, 5.0), 1.0)),
+    ]);
+
+    assert!(grid.contains(entity(1)));
+    assert!(!grid.contains(entity(3)));
+
+    let (x, y) = Shape::square(Vec2::new(4.5, 1.5), 0.75).project_aligned();
+    let mut result: Vec<Entity> = grid.query(x, y).into_iter().collect();
+    result.sort();
+    assert_eq!(result, vec![entity(2)]);
+
+    let (x, y) = Shape::rectangle(Vec2::new(-5.0, 2.0), Vec2::new(20.0, 4.0)).project_aligned();
+    let mut result: Vec<Entity> = grid.query(x, y).into_iter().collect();
+    result.sort();
+    assert_eq!(result, vec![entity(0), entity(1), entity(2)]);
+}
+
+#[test]
+fn collider_lookup_static_and_dynamic() {
+    let mut world = World::new();
+    let wall   = world.spawn().insert(Collider{ shape: Shape::rectangle(Vec2::new(0.0, -1.0), Vec2::new(10.0, 1.0)) }).insert(StaticCollider).id();
+    let player = world.spawn().insert(Collider{ shape: Shape::square(Vec2::new(0.0, 0.25), 0.5) }).id();
+
+    // A single grid unless a hierarchy is asked for
+    let mut lookup: ColliderLookup<CacheGrid> = ColliderLookup::new(&mut world, 1.0);
+    lookup.update(&mut world);
+    assert!(lookup.statics().contains(wall));
+    assert!(!lookup.statics().contains(player));
+
+    let result = lookup.query(Shape::square(Vec2::new(0.0, 0.0), 0.1));
+    assert!(result.contains(&wall) && result.contains(&player));
+    assert_eq!(lookup.query_pairs().into_iter().collect::<Vec<_>>(), vec![(wall.min(player), wall.max(player))]);
+
+    world.despawn(wall);
+    lookup.update(&mut world);
+    world.clear_trackers();
+    assert!(!lookup.statics().contains(wall));
+    assert_eq!(lookup.query(Shape::square(Vec2::new(0.0, 0.0), 0.1)).into_iter().collect::<Vec<_>>(), vec![player]);
+}
+
+#[test]
+fn broadphase_cache_grid_bounded() {
+    check_broadphase(CacheGrid::new_bounded(0.25, Projection::new(-50.0, 50.0), Projection::new(-50.0, 50.0)), false);
+}
+
+#[test]
+fn cache_grid_generic_key() {
+    let mut grid = CacheGrid::<usize>::new(1.0);
+    grid.update(3, Shape::circle(Vec2::new(0.5, 0.5), 0.25));
+    grid.update_bounds(7, Projection::new(0.0, 2.0), Projection::new(0.0, 2.0));
+
+    let mut result: Vec<usize> = grid.query(Projection::new(0.25, 0.75), Projection::new(0.25, 0.75)).into_iter().collect();
+    result.sort_unstable();
+    assert_eq!(result, vec![3, 7]);
+
+    let mut pairs: HashSet<(usize, usize)> = Default::default();
+    grid.query_pairs_into(&mut pairs);
+    assert_eq!(pairs.into_iter().collect::<Vec<_>>(), vec![(3, 7)]);
+}
+
+#[test]
+fn collider_lookup_parallel_update() {
+    let shapes = scatter(2000);
+
+    let mut world = World::new();
+    world.insert_resource(ComputeTaskPool(TaskPool::new()));
+    let entities: Vec<Entity> = shapes.iter().map(|&(_, shape)| world.spawn().insert(Collider{ shape }).id()).collect();
+
+    let mut lookup = ColliderLookup::new(&mut world, 0.5);
+    lookup.update(&mut world);
+
+    for (&entity, &(_, shape)) in entities.iter().zip(&shapes) {
+        assert!(lookup.query(shape).contains(&entity), "Parallel update missed entity");
+    }
+    let named: Vec<(Entity, Shape)> = entities.iter().copied().zip(shapes.iter().map(|&(_, shape)| shape)).collect();
+    assert!(exact_pairs(&named).is_subset(&lookup.query_pairs()));
+}
+
+#[test]
+fn cache_grid_snapshot_restore() {
+    let mut grid = CacheGrid::new(0.5);
+    let shapes = scatter(200);
+    for &(entity, shape) in &shapes { grid.update(entity, shape); }
+
+    let snapshot = grid.snapshot();
+    let pairs    = Broadphase::query_pairs(&grid);
+    let order: Vec<Entity> = grid.iter_bounds().map(|(entity, _, _)| entity).collect();
+
+    for &(entity, shape) in shapes.iter().step_by(2) {
+        grid.update(entity, Shape{ origin: shape.origin + Vec2::new(7.0, 3.0), ..shape });
+    }
+    for &(entity, _) in shapes.iter().step_by(3) { grid.remove(entity); }
+    assert_ne!(pairs, Broadphase::query_pairs(&grid));
+
+    grid.restore(&snapshot);
+    assert_eq!(pairs, Broadphase::query_pairs(&grid));
+    assert_eq!(order, grid.iter_bounds().map(|(entity, _, _)| entity).collect::<Vec<_>>());
+}
+
+#[test]
+fn collider_lookup_snapshot_restore() {
+    let mut world = World::new();
+    let entity = world.spawn().insert(Collider{ shape: Shape::square(Vec2::new(0.5, 0.5), 0.25) }).id();
+    let mut lookup = ColliderLookup::new(&mut world, 1.0);
+    lookup.update(&mut world);
+    let snapshot = lookup.snapshot();
+
+    world.get_mut::<Collider>(entity).unwrap().shape.origin = Vec2::new(10.5, 0.5);
+    lookup.update(&mut world);
+    assert!(lookup.query(Shape::square(Vec2::new(0.5, 0.5), 0.1)).is_empty());
+
+    lookup.restore(&snapshot);
+    assert!(lookup.query(Shape::square(Vec2::new(0.5, 0.5), 0.1)).contains(&entity));
+}
+
+#[test]
+fn broadphase_query_sorted() {
+    let shapes = scatter(300);
+    let mut grid = CacheGridHierarchy::new(1.0, 4);
+    let mut tree = AabbTree::new(0.5);
+    for &(entity, shape) in &shapes {
+        grid.update(entity, shape);
+        tree.update(entity, shape);
+    }
+
+    let (x, y) = Shape::square(Vec2::ZERO, 40.0).project_aligned();
+    for sorted in [Broadphase::query_sorted(&grid, x, y), tree.query_sorted(x, y)] {
+        assert!(!sorted.is_empty());
+        assert!(sorted.windows(2).all(|v| v[0] < v[1]));
+    }
+
+    let mut expected: Vec<Entity> = grid.query(x, y).into_iter().collect();
+    expected.sort();
+    assert_eq!(expected, grid.query_sorted(x, y));
+    assert!(tree.query_pairs_sorted().windows(2).all(|v| v[0] < v[1]));
+}
+
+#[test]
+fn cache_grid_stats() {
+    let mut grid = CacheGrid::new(1.0);
+    grid.update(entity(0), Shape::rectangle(Vec2::new(1.0, 0.5), Vec2::new(1.0, 0.5)));
+    grid.update(entity(1), Shape::square(Vec2::new(0.5, 0.5), 0.25));
+    grid.update(entity(2), Shape::square(Vec2::new(5.5, 5.5), 0.25));
+    grid.query(Projection::new(0.25, 0.75), Projection::new(0.25, 0.75));
+
+    let stats = grid.stats();
+    assert_eq!(stats.entities, 3);
+    assert_eq!(stats.occupied_cells, 3);
+    assert_eq!(stats.cell_entries, 4);
+    assert_eq!(stats.cell_histogram[1..3], [2, 1]);
+    assert_eq!(stats.queries, 1);
+    assert_eq!(stats.candidates, 2);
+    assert!((stats.cells_per_entity() - 4.0/3.0).abs() < f32::EPSILON);
+
+    grid.remove(entity(2));
+    grid.clear_query_counts();
+    let stats = grid.stats();
+    assert_eq!((stats.freelist, stats.queries), (1, 0));
+}
+
+#[test]
+fn cache_grid_stats_diagnostics() {
+    let mut grid = CacheGridHierarchy::new(1.0, 2);
+    grid.update(entity(0), Shape::square(Vec2::new(0.5, 0.5), 0.25));
+    grid.update(entity(1), Shape::square(Vec2::new(0.5, 0.5), 1.5));
+    assert_eq!(grid.query_pairs().len(), 1);
+    assert_eq!(grid.stats().queries, 0); // Internal queries aren't counted
+
+    let mut app = App::new();
+    app.insert_resource(Diagnostics::default()).add_plugin(CacheGridDiagnosticsPlugin);
+    app.world.spawn().insert(Collider{ shape: Shape::square(Vec2::new(0.5, 0.5), 0.25) });
+    let mut lookup = ColliderLookup::<'static>::new(&mut app.world, 1.0);
+    lookup.update(&mut app.world);
+    lookup.query(Shape::point(Vec2::new(0.5, 0.5)));
+    app.insert_resource(lookup);
+    app.update();
+
+    let diagnostics = app.world.resource::<Diagnostics>();
+    let value = |id| diagnostics.get(id).and_then(Diagnostic::value);
+    assert_eq!(value(CacheGridDiagnosticsPlugin::QUERIES), Some(1.0));
+    assert_eq!(value(CacheGridDiagnosticsPlugin::CELL_HISTOGRAM[1]), Some(1.0));
+    assert_eq!(value(CacheGridDiagnosticsPlugin::CELL_HISTOGRAM[2]), Some(0.0));
+    assert_eq!(app.world.resource::<ColliderLookup<'static>>().broadphase().stats().queries, 0);
+}
+
+#[test]
+fn collider_lookup_spaces() {
+    let mut world = World::new();
+    let wall   = world.spawn().insert(Collider{ shape: Shape::rectangle(Vec2::new(0.0, -1.0), Vec2::new(10.0, 1.0)) }).insert(StaticCollider).id();
+    let player = world.spawn().insert(Collider{ shape: Shape::square(Vec2::new(0.0, 0.25), 0.5) }).id();
+    let other  = world.spawn().insert(Collider{ shape: Shape::square(Vec2::new(0.0, 0.25), 0.5) }).insert(ColliderSpace(1)).id();
+
+    let mut lookup_default = ColliderLookup::new(&mut world, 1.0);
+    let mut lookup_other   = ColliderLookup::new_in_space(&mut world, 1.0, ColliderSpace(1));
+    let update = |world: &mut World, lookups: [&mut ColliderLookup; 2]| {
+        lookups.into_iter().for_each(|lookup| lookup.update(world));
+        world.clear_trackers();
+    };
+    let shape = Shape::square(Vec2::new(0.0, 0.0), 0.1);
+
+    update(&mut world, [&mut lookup_default, &mut lookup_other]);
+    assert_eq!(lookup_default.query_sorted(shape), into_sorted([wall, player].into_iter().collect()));
+    assert_eq!(lookup_other.query_sorted(shape), vec![other]);
+    assert!(lookup_other.query_pairs().is_empty());
+
+    // Move the player and wall into the other space
+    world.entity_mut(player).insert(ColliderSpace(1));
+    world.entity_mut(wall).insert(ColliderSpace(1));
+    update(&mut world, [&mut lookup_default, &mut lookup_other]);
+    assert!(lookup_default.query(shape).is_empty());
+    assert_eq!(lookup_other.query_sorted(shape), into_sorted([wall, player, other].into_iter().collect()));
+
+    // Removing the component returns them to the default space
+    world.entity_mut(player).remove::<ColliderSpace>();
+    world.entity_mut(wall).remove::<ColliderSpace>();
+    update(&mut world, [&mut lookup_default, &mut lookup_other]);
+    assert_eq!(lookup_default.query_sorted(shape), into_sorted([wall, player].into_iter().collect()));
+    assert_eq!(lookup_other.query_sorted(shape), vec![other]);
+
+    // Despawning a dynamic collider leaves the statics alone, even while shared with a snapshot
+    let (_snapshot, statics) = (lookup_other.snapshot(), lookup_other.statics() as *const StaticGrid);
+    world.despawn(other);
+    update(&mut world, [&mut lookup_default, &mut lookup_other]);
+    assert!(lookup_other.query(shape).is_empty());
+    assert!(std::ptr::eq(lookup_other.statics(), statics));
+}
+
+/// An app with the physics plugin, where time never advances as the core
+/// plugin isn't added, so steps only happen through [run_physics_step]
+fn physics_app() -> App {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .init_resource::<FixedTimesteps>()
+        .add_plugin(PhysicsPlugin::default());
+    app
+}
+
+/// Runs every stage of a single physics step, skipping the fixed timestep
+fn run_physics_step(app: &mut App) {
+    let schedule = app.schedule.get_stage_mut::<Schedule>(&PhysicsSchedule).unwrap();
+    for stage in [PhysicsStage::Prepare, PhysicsStage::Step, PhysicsStage::Lookup, PhysicsStage::Resolve] {
+        schedule.get_stage_mut::<SystemStage>(&stage).unwrap().run(&mut app.world);
+    }
+}
+
+#[test]
+fn physics_plugin_fixed_step() {
+    fn movement_system(mut query: Query<&mut PhysicsPosition>) {
+        query.iter_mut().for_each(|mut position| position.current.x += 0.25);
+    }
+
+    let mut app = physics_app();
+    app.add_physics_system(PhysicsStage::Step, movement_system);
+
+    let entity = app.world.spawn()
+        .insert(PhysicsPosition::new(Vec2::ZERO))
+        .insert(Collider{ shape: Shape::square(Vec2::ZERO, 0.5) })
+        .insert(Transform::default())
+        .id();
+    let resolved = app.world.spawn()
+        .insert(PhysicsPosition::new(Vec2::new(0.0, 5.0)))
+        .insert(Collider{ shape: Shape::square(Vec2::new(0.0, 5.0), 0.5) })
+        .insert(ResolveStatic)
+        .id();
+    app.world.spawn()
+        .insert(Collider{ shape: Shape::rectangle(Vec2::new(0.0, 4.25), Vec2::new(10.0, 0.5)) })
+        .insert(StaticCollider);
+
+    // No time has passed, so no step is due
+    app.update();
+    assert_eq!(app.world.get::<PhysicsPosition>(entity).unwrap().current, Vec2::ZERO);
+
+    for _ in 0..2 { run_physics_step(&mut app); }
+    let position = *app.world.get::<PhysicsPosition>(entity).unwrap();
+    assert_eq!((position.previous.x, position.current.x), (0.25, 0.5));
+
+    let lookup = app.world.resource::<ColliderLookup<'static>>();
+    assert_eq!(lookup.query_sorted(Shape::point(Vec2::new(0.9, 0.1))), vec![entity]);
+    assert_eq!(lookup.query_sorted(Shape::point(Vec2::new(-0.5, 0.1))), vec![]);
+
+    // Interpolated from the previous position, as the next step isn't due yet
+    app.update();
+    assert_eq!(app.world.get::<Transform>(entity).unwrap().translation.x, 0.25);
+
+    // Pushed out of the static collider after the first step, then moved sideways
+    let position = app.world.get::<PhysicsPosition>(resolved).unwrap().current;
+    assert_eq!(position, Vec2::new(0.5, 5.25));
+}
+
+#[test]
+fn physics_plugin_removed_between_steps() {
+    let mut app = physics_app();
+    let dynamic = app.world.spawn().insert(Collider{ shape: Shape::square(Vec2::ZERO, 0.5) }).id();
+    let wall    = app.world.spawn().insert(Collider{ shape: Shape::square(Vec2::ZERO, 0.5) }).insert(StaticCollider).id();
+    app.update();
+    run_physics_step(&mut app);
+    assert_eq!(app.world.resource::<ColliderLookup<'static>>().query_sorted(Shape::point(Vec2::new(0.1, 0.1))), vec![dynamic, wall]);
+
+    // Despawned on frames without a step, which Bevy forgets at the end of the frame
+    app.world.despawn(dynamic);
+    app.update();
+    app.world.despawn(wall);
+    app.update();
+    run_physics_step(&mut app);
+
+    let lookup = app.world.resource::<ColliderLookup<'static>>();
+    assert_eq!(lookup.query_sorted(Shape::point(Vec2::new(0.1, 0.1))), vec![]);
+    assert!(!lookup.statics().contains(wall));
+}
+
+#[test]
+fn tilemap_merge() {
+    use Tile::{Empty as E, Solid as S};
+    const L: Tile = Tile::Slope{ incline: true, invert: false };
+
+    let tiles = [
+        S, S, S, S, S, S,
+        S, S, E, E, S, S,
+        S, S, L, E, S, E,
+    ];
+    let map = Tilemap::new(&tiles, 6, Vec2::new(2.0, 1.0), Vec2::new(-1.0, 0.0));
+    assert_eq!(map.height(), 3);
+
+    let shapes = map.shapes();
+    assert_eq!(shapes.len(), 5);
+    assert_eq!(shapes.iter().filter(|shape| shape.shape == ShapeKind::TriangleInclineNormal).count(), 1);
+
+    // Every solid tile centre is covered by exactly one rectangle
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            let centre = Vec2::new(-1.0 + 2.0*x as f32 + 1.0, y as f32 + 0.5);
+            let covering = shapes.iter().filter(|shape| shape.shape == ShapeKind::Rectangle && {
+                let (px, py) = shape.project_aligned();
+                px.near() < centre.x && centre.x < px.far() && py.near() < centre.y && centre.y < py.far()
+            }).count();
+            assert_eq!(covering, (map.get(x, y) == S) as usize);
+        }
+    }
+
+    // Merged sides are only internal where a neighbour covers them
+    let shapes = map.shapes_with_edges();
+    let (_, floor) = shapes.iter().find(|(shape, _)| shape.origin == Vec2::new(5.0, 0.5)).unwrap();
+    assert_eq!(floor.spans(Side::Top), [Projection::new(-1.0, 3.0), Projection::new(7.0, 11.0)]);
+    assert!(floor.spans(Side::Left).is_empty() && floor.spans(Side::Bottom).is_empty());
+}
+
+#[test]
+fn tilemap_inverted_slope_edges() {
+    use Tile::Solid as S;
+    const DECLINE_INVERT: Tile = Tile::Slope{ incline: false, invert: true  };
+    const INCLINE_INVERT: Tile = Tile::Slope{ incline: true,  invert: true  };
+
+    // A slope covers the sides its triangle has two corners on
+    for (incline, invert) in [(false, false), (true, false), (false, true), (true, true)] {
+        let points = Shape::triangle(Vec2::ZERO, Vec2::ONE, incline, invert).get_points_slope();
+        let on = |side: Side| points.iter().filter(|point| match side {
+            Side::Left   => point.x == -1.0,
+            Side::Right  => point.x ==  1.0,
+            Side::Bottom => point.y == -1.0,
+            Side::Top    => point.y ==  1.0,
+        }).count() == 2;
+        for side in Side::ALL {
+            assert_eq!(Tile::Slope{ incline, invert }.covers(side), on(side), "{} {} {:?}", incline, invert, side);
+        }
+    }
+
+    // Only the side of the solid tile facing the covered side of the slope is internal
+    let edges = |tiles: &[Tile]| {
+        let shapes = Tilemap::new(tiles, 3, Vec2::ONE, Vec2::ZERO).shapes_with_edges();
+        let find = |x: f32| shapes.iter().find(|(shape, _)| shape.origin == Vec2::new(x, 0.5)).unwrap().1.clone();
+        (find(0.5), find(2.5))
+    };
+    let (left, right) = edges(&[S, DECLINE_INVERT, S]);
+    assert!(left.spans(Side::Right).is_empty());
+    assert_eq!(right.spans(Side::Left), [Projection::new(0.0, 1.0)]);
+
+    let (left, right) = edges(&[S, INCLINE_INVERT, S]);
+    assert_eq!(left.spans(Side::Right), [Projection::new(0.0, 1.0)]);
+    assert!(right.spans(Side::Left).is_empty());
+}
+
+#[test]
+fn tilemap_internal_edges_prevent_snag() {
+    use Tile::{Empty as E, Solid as S};
+
+    // Pillars break the floor into separate colliders, with seams at x = 1 and x = 3
+    let tiles = [
+        S, E, E, S,
+        S, S, S, S,
+    ];
+    let map = Tilemap::new(&tiles, 4, Vec2::ONE, Vec2::ZERO);
+    let shapes = map.shapes_with_edges();
+    assert_eq!(shapes.len(), 3);
+
+    let (middle, edges) = shapes.iter().find(|(shape, _)| shape.origin == Vec2::new(2.0, 1.5)).unwrap();
+    assert_eq!(edges.spans(Side::Left),  [Projection::new(1.0, 2.0)]);
+    assert_eq!(edges.spans(Side::Right), [Projection::new(1.0, 2.0)]);
+
+    // A box sliding along the floor, just past the seam and sunk slightly into it
+    let player = Shape::square(Vec2::new(0.76, 2.2), 0.25);
+    let snag = player.get_penetration(middle).unwrap();
+    assert!(snag.x < 0.0 && snag.y == 0.0);
+
+    let push = player.get_penetration_with_edges(middle, edges).unwrap();
+    assert!(push.x == 0.0 && (push.y - 0.05).abs() < 1e-4);
+
+    // Resolving against the whole floor only ever pushes upwards
+    let mut world = World::new();
+    let entities: Vec<Entity> = map.colliders().map(|(collider, edges)| world.spawn().insert(collider).insert(edges).insert(StaticCollider).id()).collect();
+    let mut lookup = ColliderLookup::new(&mut world, 1.0);
+    lookup.update(&mut world);
+
+    for x in [0.76, 1.24, 2.76, 3.24] {
+        let push = lookup.resolve(Shape::square(Vec2::new(x, 2.2), 0.25), |entity| {
+            assert!(entities.contains(&entity));
+            Some((world.get::<Collider>(entity)?.shape, world.get::<ColliderEdges>(entity).map(|edges| &edges.0)))
+        });
+        assert!(push.x == 0.0 && (push.y - 0.05).abs() < 1e-4, "{} {}", x, push);
+    }
+}
+
+#[test]
+fn debug_lines_geometry() {
+    let mut lines = DebugLines::new();
+    lines.add_shape(&Shape::rectangle(Vec2::new(1.0, 1.0), Vec2::new(1.0, 0.5)), 16);
+    lines.add_shape(&Shape::triangle(Vec2::ZERO, Vec2::ONE, true, false), 16);
+    lines.add_shape(&Shape::circle(Vec2::ZERO, 1.0), 16);
+    assert_eq!(lines.len(), 4 + 3 + 16);
+    assert_eq!(lines.positions().len(), 2*lines.len());
+
+    // Each outline is closed, so every point starts exactly one line and ends one
+    let rectangle = &lines.lines()[..4];
+    assert!(rectangle.iter().all(|&[a, _]| rectangle.iter().filter(|&&[_, b]| a == b).count() == 1));
+    assert_eq!(rectangle[0][0], Vec2::new(0.0, 0.5));
+
+    let mut grid = CacheGrid::new(1.0);
+    grid.update(entity(0), Shape::square(Vec2::new(0.5, 0.5), 0.25));
+    grid.update(entity(1), Shape::rectangle(Vec2::new(2.0, 0.5), Vec2::new(0.5, 0.25)));
+    lines.clear();
+    lines.add_cells(&grid);
+    assert_eq!(lines.len(), 4*3);
+
+    lines.clear();
+    lines.add_contact(Vec2::ZERO, Vec2::new(0.0, 1.0), 0.1);
+    assert_eq!(lines.lines()[2], [Vec2::ZERO, Vec2::new(0.0, 1.0)]);
+
+    let mut contacts = DebugContacts::default();
+    contacts.add(Vec2::ZERO, Vec2::Y);
+    contacts.tick(0.25);
+    assert_eq!(contacts.iter().count(), 1);
+    contacts.tick(0.5);
+    assert_eq!(contacts.iter().count(), 0);
+}
+
+#[test]
+fn collider_reflect_scene() {
+    use bevy::{
+        ecs::entity::EntityMap,
+        reflect::{GetPath, TypeRegistryArc},
+        scene::DynamicScene,
+    };
+
+    let build = || {
+        let mut app = App::new();
+        app.add_plugin(ColliderReflectPlugin);
+        app
+    };
+
+    let mut source = build();
+    let shape = Shape::triangle(Vec2::new(1.0, 2.0), Vec2::new(0.5, 0.25), true, false);
+    source.world.spawn().insert(Collider{ shape }).insert(StaticCollider).insert(ColliderSpace(2));
+
+    let registry = source.world.resource::<TypeRegistryArc>().clone();
+    let scene = DynamicScene::from_world(&source.world, &registry);
+    if cfg!(feature = "serde") {
+        assert!(scene.serialize_ron(&registry).unwrap().contains("incline"));
+    }
+
+    let mut target = build();
+    scene.write_to_world(&mut target.world, &mut EntityMap::default()).unwrap();
+    let (entity, mut collider) = {
+        let mut query = target.world.query::<(Entity, &Collider, &StaticCollider, &ColliderSpace)>();
+        let (entity, collider, _, &space) = query.iter(&target.world).next().unwrap();
+        assert_eq!(space, ColliderSpace(2));
+        (entity, Collider{ shape: collider.shape })
+    };
+    assert_eq!((collider.shape.origin, collider.shape.hbound, collider.shape.shape), (shape.origin, shape.hbound, shape.shape));
+
+    // Fields can be edited by path, as an inspector would
+    *collider.get_path_mut::<Vec2>("shape.origin").unwrap() = Vec2::ZERO;
+    *collider.get_path_mut::<ShapeKind>("shape.shape").unwrap() = ShapeKind::Ellipse;
+    target.world.entity_mut(entity).insert(collider);
+    let collider = target.world.get::<Collider>(entity).unwrap();
+    assert_eq!((collider.shape.origin, collider.shape.shape), (Vec2::ZERO, ShapeKind::Ellipse));
+}
@@ -0,0 +1,162 @@
+/*========================================================================*\
+** NotVeryMoe BevyUtil | Copyright 2021 NotVeryMoe (projects@notvery.moe) **
+\*========================================================================*/
+
+use crate::{
+    math::Vec2,
+    collision::{Shape, InternalEdges, Projection, Side},
+};
+
+use super::{Collider, ColliderEdges};
+
+/// The collision of a single tile, slopes match the arguments of [Shape::triangle]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tile {
+    #[default]
+    Empty,
+    Solid,
+    Slope{ incline: bool, invert: bool },
+}
+
+impl Tile {
+    /// Whether a side of the tile is completely covered
+    pub fn covers(self, side: Side) -> bool {
+        match self {
+            Tile::Empty => false,
+            Tile::Solid => true,
+            // Inverting a slope flips it vertically, moving its corner to the other side
+            Tile::Slope{ incline, invert } => side == (if incline != invert { Side::Right } else { Side::Left })
+                || side == (if invert { Side::Top } else { Side::Bottom }),
+        }
+    }
+}
+
+/// A grid of [Tile]s stored row by row, with the first row at the bottom.
+/// Used to generate a small number of colliders covering every tile.
+#[derive(Debug, Clone, Copy)]
+pub struct Tilemap<'a> {
+    tiles:     &'a [Tile],
+    width:     usize,
+    tile_size: Vec2,
+    origin:    Vec2,
+}
+
+impl<'a> Tilemap<'a> {
+    /// Creates a new tilemap.
+    ///
+    /// # Arguments
+    ///
+    /// * `tiles` The tiles row by row, the length must be a multiple of the width
+    /// * `width` The number of tiles in each row
+    /// * `tile_size` The full size of each tile
+    /// * `origin` The position of the bottom-left corner of the first tile
+    pub fn new(tiles: &'a [Tile], width: usize, tile_size: Vec2, origin: Vec2) -> Self {
+        assert!(width > 0 && tiles.len().is_multiple_of(width), "Tile count must be a multiple of the width");
+        Self{ tiles, width, tile_size, origin }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.tiles.len()/self.width
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Tile {
+        self.tiles[y*self.width + x]
+    }
+
+    /// Generates shapes covering the tilemap. Runs of solid tiles are greedily
+    /// merged into rectangles, growing along rows and then upwards, while each
+    /// slope becomes a triangle.
+    pub fn shapes(&self) -> Vec<Shape> {
+        self.blocks().into_iter().map(|(shape, ..)| shape).collect()
+    }
+
+    /// Like [Self::shapes], along with the parts of each shape's sides which
+    /// are shared with neighbouring tiles.
+    pub fn shapes_with_edges(&self) -> Vec<(Shape, InternalEdges)> {
+        self.blocks().into_iter().map(|(shape, x, y, width, height)| (shape, self.internal_edges(x, y, width, height))).collect()
+    }
+
+    /// Generates colliders covering the tilemap, see [Self::shapes_with_edges]
+    pub fn colliders(&self) -> impl Iterator<Item = (Collider, ColliderEdges)> {
+        self.shapes_with_edges().into_iter().map(|(shape, edges)| (Collider{ shape }, ColliderEdges(edges)))
+    }
+
+    /// Generates the shapes covering the tilemap, along with the block of tiles
+    /// covered by each as (x, y, width, height)
+    fn blocks(&self) -> Vec<(Shape, usize, usize, usize, usize)> {
+        let (width, height) = (self.width, self.height());
+        let mut merged = vec![false; self.tiles.len()];
+        let mut result = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                if merged[y*width + x] { continue; }
+                match self.get(x, y) {
+                    Tile::Empty => {},
+                    Tile::Slope{ incline, invert } => {
+                        let (origin, hbound) = self.bounds(x, y, 1, 1);
+                        result.push((Shape::triangle(origin, hbound, incline, invert), x, y, 1, 1));
+                    },
+                    Tile::Solid => {
+                        let free = |x: usize, y: usize| !merged[y*width + x] && self.get(x, y) == Tile::Solid;
+
+                        let run = (x..width).take_while(|&x| free(x, y)).count();
+                        let rows = 1 + (y+1..height).take_while(|&y| (x..x+run).all(|x| free(x, y))).count();
+                        for y in y..y+rows {
+                            merged[y*width + x..y*width + x + run].fill(true);
+                        }
+
+                        let (origin, hbound) = self.bounds(x, y, run, rows);
+                        result.push((Shape::rectangle(origin, hbound), x, y, run, rows));
+                    },
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Whether a side of the tile is fully covered and meets a fully covered
+    /// side of its neighbour.
+    fn shares_side(&self, x: usize, y: usize, side: Side) -> bool {
+        let neighbour = match side {
+            Side::Left   => x.checked_sub(1).map(|x| (x, y)),
+            Side::Right  => Some((x + 1, y)),
+            Side::Bottom => y.checked_sub(1).map(|y| (x, y)),
+            Side::Top    => Some((x, y + 1)),
+        };
+        match neighbour {
+            Some((nx, ny)) if nx < self.width && ny < self.height() => {
+                self.get(x, y).covers(side) && self.get(nx, ny).covers(side.opposite())
+            },
+            _ => false,
+        }
+    }
+
+    /// The parts of the sides of a block of tiles which are shared, tile by tile
+    fn internal_edges(&self, x: usize, y: usize, width: usize, height: usize) -> InternalEdges {
+        let mut result = InternalEdges::new();
+        for y in y..y+height {
+            let span = Projection::new(self.origin.y + self.tile_size.y*y as f32, self.origin.y + self.tile_size.y*(y + 1) as f32);
+            if self.shares_side(x,         y, Side::Left ) { result.add(Side::Left,  span); }
+            if self.shares_side(x+width-1, y, Side::Right) { result.add(Side::Right, span); }
+        }
+        for x in x..x+width {
+            let span = Projection::new(self.origin.x + self.tile_size.x*x as f32, self.origin.x + self.tile_size.x*(x + 1) as f32);
+            if self.shares_side(x, y,          Side::Bottom) { result.add(Side::Bottom, span); }
+            if self.shares_side(x, y+height-1, Side::Top   ) { result.add(Side::Top,    span); }
+        }
+        result
+    }
+
+    /// The origin and half-size of a block of tiles
+    fn bounds(&self, x: usize, y: usize, width: usize, height: usize) -> (Vec2, Vec2) {
+        let hbound = 0.5*self.tile_size*Vec2::new(width as f32, height as f32);
+        let origin = self.origin + self.tile_size*Vec2::new(x as f32, y as f32) + hbound;
+        (origin, hbound)
+    }
+}